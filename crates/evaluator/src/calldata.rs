@@ -0,0 +1,327 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::{ActionKind, ActionSig};
+
+/// Enso router and delegate contracts, same address on every supported chain.
+const ENSO_DEPLOYMENTS: &[&str] = &[
+    // EnsoRouter
+    "0xf75584ef6673ad213a685a1b58cc0330b8ea22cf",
+    // EnsoDelegate (routingStrategy=delegate)
+    "0x7663fd40081dccd47805c00e613b6beac3b87f08",
+    // EnsoShortcutRouter (v1)
+    "0x80eba3855878739f4710233a8a19d89bdd2ffb8e",
+];
+
+/// `routeSingle((uint8,bytes),bytes)`
+const ROUTE_SINGLE: &str = "b94c3609";
+/// `routeMulti((uint8,bytes)[],bytes)`
+const ROUTE_MULTI: &str = "f52e33f5";
+/// `safeRouteSingle((uint8,bytes),(uint8,bytes),address,bytes)`
+const SAFE_ROUTE_SINGLE: &str = "21025a06";
+/// `executeShortcut(bytes32,bytes32,bytes32[],bytes[])`
+const EXECUTE_SHORTCUT: &str = "95352c9f";
+/// `executeShortcut(bytes32[],bytes[])`, the pre-v2 delegate.
+const EXECUTE_SHORTCUT_V1: &str = "fdb09f3c";
+
+/// Selectors that identify an action kind when they show up in a command.
+const ACTION_SELECTORS: &[(&str, ActionKind)] = &[
+    ("0x095ea7b3", ActionKind::Approve),
+    // Aave v3 supply / borrow / repay / withdraw
+    ("0x617ba037", ActionKind::Deposit),
+    ("0xa415bcad", ActionKind::Borrow),
+    ("0x573ade81", ActionKind::Repay),
+    ("0x69328dec", ActionKind::Redeem),
+    // ERC-4626 deposit / mint / redeem / withdraw
+    ("0x6e553f65", ActionKind::Deposit),
+    ("0x94bf804d", ActionKind::Deposit),
+    ("0xba087652", ActionKind::Redeem),
+    ("0xb460af94", ActionKind::Redeem),
+    // Uniswap v3 exactInputSingle (SwapRouter, SwapRouter02) / exactInput, v2, Curve
+    ("0x414bf389", ActionKind::Swap),
+    ("0x04e45aaf", ActionKind::Swap),
+    ("0xc04b8d59", ActionKind::Swap),
+    ("0x38ed1739", ActionKind::Swap),
+    ("0x3df02124", ActionKind::Swap),
+    // Lido submit, generic stake / getReward
+    ("0xa1903eab", ActionKind::Stake),
+    ("0xa694fc3a", ActionKind::Stake),
+    ("0x3d18b912", ActionKind::Harvest),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryPoint {
+    RouteSingle,
+    RouteMulti,
+    SafeRouteSingle,
+    ExecuteShortcut,
+}
+
+/// How a weiroll command invokes its target (the low bits of the flags byte).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallType {
+    DelegateCall,
+    Call,
+    StaticCall,
+    CallWithValue,
+}
+
+/// One weiroll command of a shortcut.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Command {
+    pub target: String,
+    pub selector: String,
+    pub call_type: CallType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedCalldata {
+    pub entry_point: EntryPoint,
+    pub commands: Vec<Command>,
+}
+
+/// What the envelope's calldata says about the transaction, see [`check_calldata`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalldataCheck {
+    /// `tx.to` is not a known Enso router or delegate (SPEC §9 no-hardcoding).
+    pub non_enso_to: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_point: Option<EntryPoint>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<Command>,
+    /// Claimed actions without a state-changing command to account for them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmatched_actions: Vec<ActionSig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn is_enso_deployment(address: &str, extra: &[String]) -> bool {
+    ENSO_DEPLOYMENTS
+        .iter()
+        .any(|known| known.eq_ignore_ascii_case(address))
+        || extra.iter().any(|known| known.eq_ignore_ascii_case(address))
+}
+
+/// Decodes router (`routeSingle`, `routeMulti`, `safeRouteSingle`) and delegate
+/// (`executeShortcut`) calldata down to the shortcut's weiroll commands.
+pub fn decode(data: &str) -> Result<DecodedCalldata, String> {
+    let bytes = decode_hex(data)?;
+    let (selector, args) = split_selector(&bytes)?;
+    // Router entry points wrap the delegate call in their trailing `bytes data`.
+    let inner = |data_arg: usize| -> Result<Vec<Command>, String> {
+        let inner = read_bytes(args, word_usize(args, data_arg)?)?;
+        let (selector, args) = split_selector(inner)?;
+        shortcut_commands(selector, args)
+    };
+    let (entry_point, commands) = match selector.as_str() {
+        ROUTE_SINGLE => (EntryPoint::RouteSingle, inner(1)?),
+        ROUTE_MULTI => (EntryPoint::RouteMulti, inner(1)?),
+        SAFE_ROUTE_SINGLE => (EntryPoint::SafeRouteSingle, inner(3)?),
+        _ => (EntryPoint::ExecuteShortcut, shortcut_commands(selector, args)?),
+    };
+    Ok(DecodedCalldata {
+        entry_point,
+        commands,
+    })
+}
+
+/// Flags a non-Enso `tx.to`, and pairs each claimed action with a command: first one whose
+/// selector is known for the action kind, otherwise any unused state-changing command.
+pub fn check_calldata(
+    to: &str,
+    data: &str,
+    claimed: &[ActionSig],
+    extra_deployments: &[String],
+) -> CalldataCheck {
+    let mut check = CalldataCheck {
+        non_enso_to: !is_enso_deployment(to, extra_deployments),
+        entry_point: None,
+        commands: Vec::new(),
+        unmatched_actions: Vec::new(),
+        error: None,
+    };
+    let decoded = match decode(data) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            check.error = Some(err);
+            return check;
+        }
+    };
+
+    let mut unused: Vec<&Command> = decoded
+        .commands
+        .iter()
+        .filter(|command| command.call_type != CallType::StaticCall)
+        .collect();
+    for claim in claimed.iter().filter(|sig| sig.action != ActionKind::Unknown) {
+        let by_selector = unused.iter().position(|command| {
+            ACTION_SELECTORS
+                .iter()
+                .any(|(selector, kind)| *selector == command.selector && *kind == claim.action)
+        });
+        let known = |command: &&Command| {
+            ACTION_SELECTORS
+                .iter()
+                .any(|(selector, _)| *selector == command.selector)
+        };
+        match by_selector.or_else(|| unused.iter().position(|command| !known(command))) {
+            Some(at) => {
+                unused.remove(at);
+            }
+            None => check.unmatched_actions.push(claim.clone()),
+        }
+    }
+    check.entry_point = Some(decoded.entry_point);
+    check.commands = decoded.commands;
+    check
+}
+
+fn shortcut_commands(selector: String, args: &[u8]) -> Result<Vec<Command>, String> {
+    let commands_arg = match selector.as_str() {
+        EXECUTE_SHORTCUT => 2,
+        EXECUTE_SHORTCUT_V1 => 0,
+        other => return Err(format!("unknown Enso entry point 0x{other}")),
+    };
+    let offset = word_usize(args, commands_arg)?;
+    let count = word_usize(&args[offset.min(args.len())..], 0)?;
+    let mut commands = Vec::new();
+    let mut index = 0;
+    while index < count {
+        let word = offset
+            .checked_add(32 * (index + 1))
+            .and_then(|start| args.get(start..start.checked_add(32)?))
+            .ok_or_else(|| "commands array runs past the calldata".to_string())?;
+        let flags = word[4];
+        commands.push(Command {
+            selector: format!("0x{}", encode_hex(&word[0..4])),
+            call_type: match flags & 0x03 {
+                0 => CallType::DelegateCall,
+                1 => CallType::Call,
+                2 => CallType::StaticCall,
+                _ => CallType::CallWithValue,
+            },
+            target: format!("0x{}", encode_hex(&word[12..32])),
+        });
+        // Extended commands keep their argument indices in the following word.
+        index += if flags & 0x40 != 0 { 2 } else { 1 };
+    }
+    Ok(commands)
+}
+
+fn split_selector(bytes: &[u8]) -> Result<(String, &[u8]), String> {
+    if bytes.len() < 4 {
+        return Err("calldata shorter than a selector".into());
+    }
+    Ok((encode_hex(&bytes[..4]), &bytes[4..]))
+}
+
+fn word_usize(args: &[u8], index: usize) -> Result<usize, String> {
+    let word = args
+        .get(index * 32..(index + 1) * 32)
+        .ok_or_else(|| format!("calldata has no argument word {index}"))?;
+    if word[..24].iter().any(|byte| *byte != 0) {
+        return Err(format!("argument word {index} is not a valid offset or length"));
+    }
+    Ok(word[24..]
+        .iter()
+        .fold(0usize, |acc, byte| (acc << 8) | *byte as usize))
+}
+
+/// ABI `bytes` at `offset`: a length word followed by the data.
+fn read_bytes(args: &[u8], offset: usize) -> Result<&[u8], String> {
+    let len = word_usize(args.get(offset..).unwrap_or_default(), 0)?;
+    let start = offset.checked_add(32);
+    start
+        .zip(start.and_then(|start| start.checked_add(len)))
+        .and_then(|(start, end)| args.get(start..end))
+        .ok_or_else(|| "bytes argument runs past the calldata".to_string())
+}
+
+fn decode_hex(data: &str) -> Result<Vec<u8>, String> {
+    let hex = data.trim().trim_start_matches("0x");
+    if !hex.len().is_multiple_of(2) {
+        return Err("calldata has an odd number of hex digits".into());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&hex[at..at + 2], 16).map_err(|_| "calldata is not hex".to_string()))
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(value: usize) -> String {
+        format!("{value:064x}")
+    }
+
+    fn command(selector: &str, flags: u8, target: &str) -> String {
+        format!(
+            "{selector}{flags:02x}{}{}",
+            "ff".repeat(7),
+            target.trim_start_matches("0x")
+        )
+    }
+
+    /// `executeShortcut(accountId, requestId, commands, state=[])`.
+    fn shortcut(commands: &[String]) -> String {
+        let commands_len = 32 * (1 + commands.len());
+        format!(
+            "{EXECUTE_SHORTCUT}{}{}{}{}{}{}{}",
+            word(0),
+            word(0),
+            word(128),
+            word(128 + commands_len),
+            word(commands.len()),
+            commands.concat(),
+            word(0)
+        )
+    }
+
+    #[test]
+    fn decodes_router_wrapped_shortcut() {
+        let usdc = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+        let pool = "0x87870bca3f3fd6335c3f4ce8392d69350b4fa4e2";
+        let inner = shortcut(&[command("095ea7b3", 1, usdc), command("617ba037", 1, pool)]);
+        let inner_len = inner.len() / 2;
+        let padded = format!("{inner}{}", "00".repeat((32 - inner_len % 32) % 32));
+        // routeSingle(tokenIn, data): the token tuple is irrelevant to decoding.
+        let data = format!(
+            "0x{ROUTE_SINGLE}{}{}{}{}{}{}{padded}",
+            word(64),
+            word(160),
+            word(1),
+            word(64),
+            word(0),
+            word(inner_len)
+        );
+
+        let decoded = decode(&data).unwrap();
+        assert_eq!(decoded.entry_point, EntryPoint::RouteSingle);
+        assert_eq!(
+            decoded.commands[1],
+            Command {
+                target: pool.into(),
+                selector: "0x617ba037".into(),
+                call_type: CallType::Call,
+            }
+        );
+
+        let claimed = [
+            ActionSig::new(1, ActionKind::Deposit, Some("aave-v3".into()), None, None),
+            ActionSig::new(1, ActionKind::Approve, None, None, None),
+            ActionSig::new(1, ActionKind::Borrow, Some("aave-v3".into()), None, None),
+        ];
+        let check = check_calldata("0xF75584eF6673aD213a685a1B58Cc0330B8eA22Cf", &data, &claimed, &[]);
+        assert!(!check.non_enso_to);
+        assert_eq!(check.unmatched_actions, [claimed[2].clone()]);
+        assert!(check_calldata("0xdead", "0xdeadbeef", &[], &[]).non_enso_to);
+    }
+}
@@ -1,55 +1,310 @@
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
 
-use anyhow::Result;
-use clap::{Parser, ValueEnum};
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
 
-use serde_json;
+use crate::{
+    config::DomainsConfig,
+    diff::diff_reports,
+    experiment::score_experiment,
+    lc_verify::LcVerifier,
+    model::ScoreReport,
+    parse::{load_run_meta, load_transactions},
+    score::{efficiency, explain, score},
+};
 
-use crate::{config::DomainsConfig, lc_verify::LcVerifier, parse::load_transactions, score::score};
+/// Written into the run directory by `score`.
+pub const SCORE_FILE: &str = "eval_score.json";
+/// Written into the run directory by `score`, one [`TxExplanation`](crate::TxExplanation) per line.
+pub const PER_TX_FILE: &str = "eval_per_tx.jsonl";
+/// Written into the run directory by `verify`.
+pub const HIAN_FILE: &str = "eval_hian.json";
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "EnsoBench evaluator")]
 pub struct EvaluatorCli {
-    /// Path to per_tx.jsonl artifact
+    #[command(subcommand)]
+    pub command: Command,
+    /// Output format
+    #[arg(long, global = true, default_value = "json")]
+    pub format: OutputFormat,
+    /// Score transactions that were never simulated instead of excluding them
+    #[arg(long, global = true)]
+    pub count_unsimulated: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Score coverage for a run and write eval_score.json and eval_per_tx.jsonl next to its per_tx.jsonl
+    Score(RunArgs),
+    /// Verify an Operation Needle run against ground truth and write eval_hian.json
+    Verify(VerifyArgs),
+    /// Per-transaction breakdown of which action signatures counted and why
+    Explain(RunArgs),
+    /// Compare the coverage of two runs
+    Diff(DiffArgs),
+    /// Score distribution across the repeats of a `--repeats` experiment
+    Aggregate(AggregateArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// Run directory or its per_tx.jsonl
     #[arg(long)]
-    pub per_tx: PathBuf,
+    pub run: PathBuf,
     /// Path to domains configuration YAML
     #[arg(long, default_value = "dataset/domains.enso.yaml")]
     pub domains: PathBuf,
-    /// Optional ground truth JSON for LC verification
+}
+
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// Run directory or its per_tx.jsonl
     #[arg(long)]
-    pub lc_ground_truth: Option<PathBuf>,
-    /// Output format
-    #[arg(long, default_value = "json")]
-    pub format: OutputFormat,
+    pub run: PathBuf,
+    /// Ground truth JSON for LC verification
+    #[arg(long)]
+    pub ground_truth: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Baseline run directory or per_tx.jsonl
+    #[arg(long)]
+    pub base: PathBuf,
+    /// Run directory or per_tx.jsonl compared against the baseline
+    #[arg(long)]
+    pub head: PathBuf,
+    /// Path to domains configuration YAML
+    #[arg(long, default_value = "dataset/domains.enso.yaml")]
+    pub domains: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct AggregateArgs {
+    /// Experiment directory (with experiment.json) written by the runner's `--repeats`
+    #[arg(long)]
+    pub experiment: PathBuf,
+    /// Path to domains configuration YAML
+    #[arg(long, default_value = "dataset/domains.enso.yaml")]
+    pub domains: PathBuf,
 }
 
 impl EvaluatorCli {
     pub fn run(&self) -> Result<()> {
-        let domains = DomainsConfig::load(&self.domains)?;
-        let transactions = load_transactions(&self.per_tx)?;
-        let report = score(&transactions, &domains);
+        match &self.command {
+            Command::Score(args) => self.score(args),
+            Command::Verify(args) => self.verify(args),
+            Command::Explain(args) => self.explain(args),
+            Command::Diff(args) => self.diff(args),
+            Command::Aggregate(args) => self.aggregate(args),
+        }
+    }
+
+    /// The domains file with command-line overrides applied.
+    fn domains(&self, path: &Path) -> Result<DomainsConfig> {
+        let mut domains = DomainsConfig::load(path)?;
+        domains.count_unsimulated |= self.count_unsimulated;
+        Ok(domains)
+    }
+
+    fn score(&self, args: &RunArgs) -> Result<()> {
+        let per_tx = per_tx_path(&args.run);
+        let domains = self.domains(&args.domains)?;
+        let report = score_run(&per_tx, &domains)?;
+        write_json(&run_dir(&per_tx).join(SCORE_FILE), &report)?;
+        write_jsonl(
+            &run_dir(&per_tx).join(PER_TX_FILE),
+            &explain(&load_transactions(&per_tx)?, &domains),
+        )?;
 
         match self.format {
             OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
-            OutputFormat::Text => println!("Coverage score: {:.2}", report.final_score),
+            OutputFormat::Text => {
+                println!("Coverage score: {:.2}", report.final_score);
+                for item in &report.penalties {
+                    println!("  -{:.2} tx #{}: {}", item.amount, item.tx_index, item.reason);
+                }
+                if let Some(efficiency) = &report.efficiency {
+                    println!(
+                        "Wall clock: {:.1}s, LLM calls: {}, tokens: {}",
+                        efficiency.wall_clock_seconds, efficiency.llm_calls, efficiency.total_tokens
+                    );
+                    if let Some(cost) = efficiency.cost_usd {
+                        println!("Cost: ${cost:.4}");
+                    }
+                    if let Some(per_dollar) = efficiency.score_per_dollar {
+                        println!("Score per dollar: {per_dollar:.2}");
+                    }
+                    if let Some(per_second) = efficiency.score_per_second {
+                        println!("Score per second: {per_second:.4}");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn verify(&self, args: &VerifyArgs) -> Result<()> {
+        let per_tx = per_tx_path(&args.run);
+        let transactions = load_transactions(&per_tx)?;
+        let result = LcVerifier::from_file(&args.ground_truth)?.verify(&transactions);
+        write_json(&run_dir(&per_tx).join(HIAN_FILE), &result)?;
+
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&result)?),
+            OutputFormat::Text => {
+                println!("LC verification: {}", if result.pass { "PASS" } else { "FAIL" });
+                println!("{}", result.reason);
+            }
+        }
+        Ok(())
+    }
+
+    fn explain(&self, args: &RunArgs) -> Result<()> {
+        let transactions = load_transactions(per_tx_path(&args.run))?;
+        let explanations = explain(&transactions, &self.domains(&args.domains)?);
+
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&explanations)?),
+            OutputFormat::Text => {
+                for tx in &explanations {
+                    let ignored = tx
+                        .ignored
+                        .as_deref()
+                        .map(|reason| format!(" (ignored: {reason})"))
+                        .unwrap_or_default();
+                    println!(
+                        "tx #{} on chain {}: {:+.2} (bonus {:.2}, penalty {:.2}){ignored}",
+                        tx.index, tx.chain_id, tx.score, tx.bonus, tx.penalty
+                    );
+                    for note in &tx.notes {
+                        println!("  note: {note}");
+                    }
+                    for item in &tx.bonuses {
+                        println!("  +{:.2} {}", item.amount, item.reason);
+                    }
+                    for action in &tx.actions {
+                        println!(
+                            "  [{}] {} via {} → {}: {}",
+                            if action.counted { "x" } else { " " },
+                            action.sig.action,
+                            action.sig.protocol.as_deref().unwrap_or("?"),
+                            action.domain,
+                            action.reason
+                        );
+                    }
+                }
+            }
         }
+        Ok(())
+    }
+
+    fn diff(&self, args: &DiffArgs) -> Result<()> {
+        let domains = self.domains(&args.domains)?;
+        let base = score_run(&per_tx_path(&args.base), &domains)?;
+        let head = score_run(&per_tx_path(&args.head), &domains)?;
+        let diff = diff_reports(&base, &head);
 
-        if let Some(path) = &self.lc_ground_truth {
-            let verifier = LcVerifier::from_file(path)?;
-            let result = verifier.verify(&transactions);
-            match self.format {
-                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&result)?),
-                OutputFormat::Text => {
-                    println!("LC verification: {}", if result.pass { "PASS" } else { "FAIL" })
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+            OutputFormat::Text => {
+                let total = &diff.final_score;
+                println!(
+                    "Coverage score: {:.2} → {:.2} ({:+.2})",
+                    total.base, total.head, total.delta
+                );
+                for (domain, delta) in &diff.by_domain {
+                    println!(
+                        "  {domain}: {:.2} → {:.2} ({:+.2})",
+                        delta.base, delta.head, delta.delta
+                    );
+                }
+                for sig in &diff.added {
+                    println!(
+                        "+ {} via {} on chain {}",
+                        sig.action,
+                        sig.protocol.as_deref().unwrap_or("?"),
+                        sig.chain_id
+                    );
+                }
+                for sig in &diff.removed {
+                    println!(
+                        "- {} via {} on chain {}",
+                        sig.action,
+                        sig.protocol.as_deref().unwrap_or("?"),
+                        sig.chain_id
+                    );
                 }
             }
         }
+        Ok(())
+    }
 
+    fn aggregate(&self, args: &AggregateArgs) -> Result<()> {
+        let report = score_experiment(&args.experiment, &self.domains(&args.domains)?)?;
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            OutputFormat::Text => {
+                let scores = &report.coverage_score;
+                println!(
+                    "Experiment {}: coverage median {:.2} (min {:.2}, max {:.2}) over {} runs, {} unique actions",
+                    report.experiment_id, scores.median, scores.min, scores.max, scores.n, report.unique_actions
+                );
+                if !report.skipped_repeats.is_empty() {
+                    println!("Skipped repeats without artifacts: {:?}", report.skipped_repeats);
+                }
+            }
+        }
         Ok(())
     }
 }
 
+/// Coverage report for one run, with efficiency when its meta.json is present.
+pub fn score_run(per_tx: &Path, domains: &DomainsConfig) -> Result<ScoreReport> {
+    let mut report = score(&load_transactions(per_tx)?, domains);
+    report.efficiency = load_run_meta(per_tx)?.map(|meta| efficiency(report.final_score, &meta));
+    Ok(report)
+}
+
+/// Accepts a run directory or a per_tx.jsonl path.
+fn per_tx_path(run: &Path) -> PathBuf {
+    if run.is_dir() {
+        run.join("per_tx.jsonl")
+    } else {
+        run.to_path_buf()
+    }
+}
+
+fn run_dir(per_tx: &Path) -> PathBuf {
+    per_tx
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("unable to write {}", path.display()))?;
+    serde_json::to_writer_pretty(file, value)?;
+    Ok(())
+}
+
+fn write_jsonl<T: Serialize>(path: &Path, values: &[T]) -> Result<()> {
+    let mut file =
+        BufWriter::new(File::create(path).with_context(|| format!("unable to write {}", path.display()))?);
+    for value in values {
+        serde_json::to_writer(&mut file, value)?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
 #[derive(Debug, Copy, Clone, ValueEnum)]
 pub enum OutputFormat {
     Json,
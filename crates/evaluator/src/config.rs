@@ -4,13 +4,41 @@ use anyhow::{Context, Result};
 use indexmap::IndexMap;
 use serde::Deserialize;
 
-use crate::model::ActionKind;
+use crate::{
+    model::{ActionKind, ActionSig},
+    normalize::{canonical_protocol, canonical_token},
+};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct DomainsConfig {
     pub version: String,
     pub per_tx_action_limit: usize,
     pub domains: IndexMap<String, DomainConfig>,
+    /// Chains the benchmark targets; used to point agents at uncovered chains.
+    #[serde(default)]
+    pub chains: Vec<u64>,
+    /// Weight of signatures no allow entry matches, scored under `unclassified`.
+    #[serde(default)]
+    pub unclassified_weight: f64,
+    /// Token symbols or addresses per class, for allow entries with `token_classes`.
+    #[serde(default)]
+    pub token_classes: IndexMap<TokenClass, Vec<String>>,
+    /// Router/delegate addresses accepted as `tx.to` besides the built-in Enso deployments.
+    #[serde(default)]
+    pub enso_deployments: Vec<String>,
+    /// Extra protocol spellings (canonical slug → aliases) on top of the built-in table.
+    #[serde(default)]
+    pub protocol_aliases: IndexMap<String, Vec<String>>,
+    /// Per-transaction bonus rates; only `composition` is on when the section is absent.
+    #[serde(default)]
+    pub bonuses: BonusConfig,
+    /// Score transactions that were never simulated instead of excluding them; reverts
+    /// and no-ops stay excluded. Set by `--count-unsimulated`.
+    #[serde(default)]
+    pub count_unsimulated: bool,
+    /// Anti-gaming deductions (SPEC §9); none apply when the section is absent.
+    #[serde(default)]
+    pub penalties: PenaltyConfig,
 }
 
 impl DomainsConfig {
@@ -23,17 +51,52 @@ impl DomainsConfig {
         Ok(config)
     }
 
+    /// First domain with an allow entry for `kind`, regardless of the entry's restrictions.
     pub fn domain_for_kind(&self, kind: &ActionKind) -> Option<String> {
         self.domains
             .iter()
             .find_map(|(domain, cfg)| cfg.allows(kind).then(|| domain.clone()))
     }
+
+    /// Domain and weight of the first allow entry matching `sig`, or
+    /// [`UNCLASSIFIED`] at `unclassified_weight` when none does.
+    pub fn classify(&self, sig: &ActionSig) -> (String, f64) {
+        self.domains
+            .iter()
+            .find_map(|(domain, cfg)| {
+                cfg.allow
+                    .iter()
+                    .find(|entry| entry.matches(sig, self))
+                    .map(|entry| (domain.clone(), entry.weight.unwrap_or(cfg.weight)))
+            })
+            .unwrap_or_else(|| (UNCLASSIFIED.to_string(), self.unclassified_weight))
+    }
+
+    /// Whether two spellings name the same protocol after alias normalization.
+    pub fn same_protocol(&self, a: &str, b: &str) -> bool {
+        canonical_protocol(a, &self.protocol_aliases) == canonical_protocol(b, &self.protocol_aliases)
+    }
+
+    fn token_in_class(&self, chain_id: u64, token: &str, class: TokenClass) -> bool {
+        let token = canonical_token(chain_id, token);
+        self.token_classes.get(&class).is_some_and(|tokens| {
+            tokens
+                .iter()
+                .any(|known| canonical_token(chain_id, known) == token)
+        })
+    }
 }
 
+/// Bucket for signatures that no allow entry matches.
+pub const UNCLASSIFIED: &str = "unclassified";
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct DomainConfig {
     pub weight: f64,
     pub allow: Vec<AllowEntry>,
+    /// Known protocols in this domain (Enso slugs); used to point agents at uncovered protocols.
+    #[serde(default)]
+    pub protocols: Vec<String>,
 }
 
 impl DomainConfig {
@@ -42,7 +105,168 @@ impl DomainConfig {
     }
 }
 
+/// One kind of action a domain scores. The optional lists restrict which signatures
+/// match (empty means any); the first matching entry across all domains wins.
 #[derive(Debug, Clone, Deserialize)]
 pub struct AllowEntry {
     pub action: ActionKind,
+    #[serde(default)]
+    pub protocols: Vec<String>,
+    #[serde(default)]
+    pub chains: Vec<u64>,
+    /// Both of the signature's tokens must be in one of these classes.
+    #[serde(default)]
+    pub token_classes: Vec<TokenClass>,
+    /// Overrides the domain weight for signatures matching this entry.
+    #[serde(default)]
+    pub weight: Option<f64>,
+}
+
+impl AllowEntry {
+    fn matches(&self, sig: &ActionSig, config: &DomainsConfig) -> bool {
+        if self.action != sig.action {
+            return false;
+        }
+        let protocol_ok = self.protocols.is_empty()
+            || sig.protocol.as_deref().is_some_and(|protocol| {
+                self.protocols
+                    .iter()
+                    .any(|allowed| config.same_protocol(allowed, protocol))
+            });
+        let chain_ok = self.chains.is_empty() || self.chains.contains(&sig.chain_id);
+        let in_classes = |token: &str| {
+            self.token_classes
+                .iter()
+                .any(|class| config.token_in_class(sig.chain_id, token, *class))
+        };
+        let tokens_ok = self.token_classes.is_empty()
+            || sig
+                .tokens
+                .as_ref()
+                .is_some_and(|(a, b)| in_classes(a) && in_classes(b));
+        protocol_ok && chain_ok && tokens_ok
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenClass {
+    Stablecoin,
+    Lst,
+    Lp,
+}
+
+/// Bonus points a successful transaction earns on top of its signatures.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BonusConfig {
+    /// Per distinct action kind beyond the first.
+    #[serde(default = "default_composition_bonus")]
+    pub composition: f64,
+    /// Once per transaction that bridges to another chain and then acts there.
+    #[serde(default)]
+    pub cross_chain: f64,
+    /// Per distinct protocol beyond the first.
+    #[serde(default)]
+    pub multi_protocol: f64,
+    /// Per distinct domain beyond the first.
+    #[serde(default)]
+    pub domain_diversity: f64,
+}
+
+impl Default for BonusConfig {
+    fn default() -> Self {
+        Self {
+            composition: default_composition_bonus(),
+            cross_chain: 0.0,
+            multi_protocol: 0.0,
+            domain_diversity: 0.0,
+        }
+    }
+}
+
+fn default_composition_bonus() -> f64 {
+    0.25
+}
+
+/// Repetition penalties applied to signatures that would otherwise earn full credit.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PenaltyConfig {
+    #[serde(default)]
+    pub cooldown: Option<CooldownConfig>,
+    /// Signatures per protocol beyond this many earn nothing.
+    #[serde(default)]
+    pub protocol_cap: Option<usize>,
+    /// Subtracted for a signature that only changes the token pair of an earlier one.
+    #[serde(default)]
+    pub near_duplicate: f64,
+}
+
+/// Per (action, protocol): the first `full_credit` signatures count fully, the k-th one
+/// after that earns `weight × decay^k`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CooldownConfig {
+    pub full_credit: usize,
+    pub decay: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_by_protocol_chain_and_token_class() {
+        let config: DomainsConfig = serde_yaml::from_str(
+            r#"
+version: "0.1.0"
+per_tx_action_limit: 6
+unclassified_weight: 0.25
+token_classes:
+  stablecoin: [USDC, "0x6B175474E89094C44Da98b954EedeAC495271d0F"]
+domains:
+  dex:
+    weight: 1.0
+    allow:
+      - action: swap
+        token_classes: [stablecoin]
+        weight: 0.5
+      - action: swap
+        protocols: [uniswap-v3]
+  bridge:
+    weight: 1.5
+    allow:
+      - action: bridge
+        chains: [10]
+"#,
+        )
+        .unwrap();
+        let sig = |action, protocol: &str, chain_id, tokens: Option<(&str, &str)>| {
+            ActionSig::new(
+                chain_id,
+                action,
+                Some(protocol.into()),
+                tokens.map(|(a, b)| (a.into(), b.into())),
+                None,
+            )
+        };
+        let stable = Some(("usdc", "0x6b175474e89094c44da98b954eedeac495271d0f"));
+
+        let classify = |sig: ActionSig| config.classify(&sig);
+        assert_eq!(
+            classify(sig(ActionKind::Swap, "curve", 1, stable)),
+            ("dex".into(), 0.5)
+        );
+        assert_eq!(
+            classify(sig(ActionKind::Swap, "Uniswap-V3", 1, Some(("USDC", "WETH")))),
+            ("dex".into(), 1.0)
+        );
+        assert_eq!(
+            classify(sig(ActionKind::Swap, "forkswap", 1, None)),
+            (UNCLASSIFIED.into(), 0.25)
+        );
+        assert_eq!(
+            classify(sig(ActionKind::Bridge, "across", 8453, None)),
+            (UNCLASSIFIED.into(), 0.25)
+        );
+        assert_eq!(config.domain_for_kind(&ActionKind::Swap).as_deref(), Some("dex"));
+    }
 }
@@ -0,0 +1,100 @@
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{ActionSig, ScoreReport};
+
+/// What changed between two scored runs of the same scenario.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunDiff {
+    pub final_score: ScoreDelta,
+    pub bonus: ScoreDelta,
+    pub penalty: ScoreDelta,
+    /// Union of both runs' domains; a missing domain counts as 0.
+    pub by_domain: IndexMap<String, ScoreDelta>,
+    /// Signatures only the head run covered.
+    pub added: Vec<ActionSig>,
+    /// Signatures only the base run covered.
+    pub removed: Vec<ActionSig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreDelta {
+    pub base: f64,
+    pub head: f64,
+    pub delta: f64,
+}
+
+impl ScoreDelta {
+    fn new(base: f64, head: f64) -> Self {
+        Self {
+            base,
+            head,
+            delta: head - base,
+        }
+    }
+}
+
+pub fn diff_reports(base: &ScoreReport, head: &ScoreReport) -> RunDiff {
+    let mut by_domain = IndexMap::new();
+    for domain in base.by_domain.keys().chain(head.by_domain.keys()) {
+        by_domain.entry(domain.clone()).or_insert_with(|| {
+            ScoreDelta::new(
+                base.by_domain.get(domain).copied().unwrap_or(0.0),
+                head.by_domain.get(domain).copied().unwrap_or(0.0),
+            )
+        });
+    }
+
+    let sigs = |report: &ScoreReport| -> IndexSet<ActionSig> {
+        report.unique_sigs.values().flatten().cloned().collect()
+    };
+    let (base_sigs, head_sigs) = (sigs(base), sigs(head));
+
+    RunDiff {
+        final_score: ScoreDelta::new(base.final_score, head.final_score),
+        bonus: ScoreDelta::new(base.bonus, head.bonus),
+        penalty: ScoreDelta::new(base.penalty, head.penalty),
+        by_domain,
+        added: head_sigs.difference(&base_sigs).cloned().collect(),
+        removed: base_sigs.difference(&head_sigs).cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ActionKind, ScoreMeta};
+
+    fn report(domain: &str, sigs: Vec<ActionSig>) -> ScoreReport {
+        ScoreReport {
+            final_score: sigs.len() as f64,
+            by_domain: IndexMap::from([(domain.to_string(), sigs.len() as f64)]),
+            bonus: 0.0,
+            penalty: 0.0,
+            penalties: Vec::new(),
+            metadata: ScoreMeta {
+                total_transactions: 1,
+                unique_action_signatures: sigs.len(),
+            },
+            unique_sigs: IndexMap::from([(domain.to_string(), sigs)]),
+            efficiency: None,
+        }
+    }
+
+    #[test]
+    fn lists_added_and_removed_signatures() {
+        let swap = ActionSig::new(1, ActionKind::Swap, Some("uniswap".into()), None, None);
+        let curve = ActionSig::new(1, ActionKind::Swap, Some("curve".into()), None, None);
+        let deposit = ActionSig::new(1, ActionKind::Deposit, Some("aave".into()), None, None);
+
+        let base = report("dex", vec![swap.clone(), curve.clone()]);
+        let head = report("lending", vec![deposit.clone()]);
+        let diff = diff_reports(&base, &head);
+
+        assert_eq!(diff.final_score.delta, -1.0);
+        assert_eq!(diff.by_domain["dex"], ScoreDelta::new(2.0, 0.0));
+        assert_eq!(diff.by_domain["lending"], ScoreDelta::new(0.0, 1.0));
+        assert_eq!(diff.added, [deposit]);
+        assert_eq!(diff.removed, [swap, curve]);
+    }
+}
@@ -0,0 +1,304 @@
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    model::{ActionKind, ActionSig},
+    normalize::canonical_token,
+};
+
+const TRANSFER: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+/// Uniswap v2 (and forks) `Swap(address,uint256,uint256,uint256,uint256,address)`.
+const UNISWAP_V2_SWAP: &str = "0xd78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822";
+/// Uniswap v3 `Swap(address,address,int256,int256,uint160,uint128,int24)`.
+const UNISWAP_V3_SWAP: &str = "0xc42079f94a6350d7e6235f29174924f928cc2ac818eb64fed8004e115fbcca67";
+/// Curve `TokenExchange(address,int128,uint256,int128,uint256)`.
+const CURVE_TOKEN_EXCHANGE: &str = "0x8b3e96f2b889fa771c53c981b40daf005f63f637f1869f707052d15a3dd97140";
+/// Curve `TokenExchangeUnderlying(address,int128,uint256,int128,uint256)`.
+const CURVE_TOKEN_EXCHANGE_UNDERLYING: &str =
+    "0xd013ca23e77a65003c2c659c5442c00c805371b7fc1ebd4c206c41d1536bd90b";
+/// Aave v3 `Supply(address,address,address,uint256,uint16)`.
+const AAVE_SUPPLY: &str = "0x2b627736bca15cd5381dcf80b0bf11fd197d01a037c52b927a881a10fb73ba61";
+/// Aave v3 `Borrow(address,address,address,uint256,uint8,uint256,uint16)`.
+const AAVE_BORROW: &str = "0xb3d084820fb1a9decffb176436bd02558d15fac9b0ddfed8c465bc7359d7dce0";
+/// Aave v3 `Repay(address,address,address,uint256,bool)`.
+const AAVE_REPAY: &str = "0xa534c8dbe71f871f9f3530e97a74601fea17b426cae02e1c5aee42c96c784051";
+/// Aave v3 `Withdraw(address,address,address,uint256)`.
+const AAVE_WITHDRAW: &str = "0x3115d1449a7b732c986cba18244e897a450f61e1bb8d589cd2e69e6c8924f9f7";
+/// ERC-4626 `Deposit(address,address,uint256,uint256)`.
+const ERC4626_DEPOSIT: &str = "0xdcbc1c05240f31ff3ad067ef1ee35ce4997762752e3a095284754544f4c709d7";
+/// ERC-4626 `Withdraw(address,address,address,uint256,uint256)`.
+const ERC4626_WITHDRAW: &str = "0xfbde797d201c681b91056529119e0b02407c7bb96a4a2c75c01fc9667232c8db";
+/// Stargate v1 `Swap(uint16,uint256,address,uint256,uint256,uint256,uint256,uint256)`.
+const STARGATE_SWAP: &str = "0x34660fc8af304464529f48a778e03d03e4d34bcd5f9b6f0cfbf3cd238c642f7f";
+/// LayerZero v2 OFT (incl. Stargate v2) `OFTSent(bytes32,uint32,address,uint256,uint256)`.
+const OFT_SENT: &str = "0x85496b760a4b7f8d66384b9df21b381f5d1b1e79f229a47aaf4c232edc2fe59a";
+/// LayerZero v2 endpoint `PacketSent(bytes,bytes,address)`.
+const PACKET_SENT: &str = "0x1ab700d4ced0c005b164c0f789fd09fcbb0156d4c2041b8a3bfbcd961cd1567f";
+
+/// LayerZero v1 chain ids and v2 endpoint ids of the benchmark chains.
+const LAYERZERO_CHAINS: &[(u64, u64)] = &[
+    (101, 1),
+    (111, 10),
+    (109, 137),
+    (184, 8453),
+    (110, 42161),
+    (30101, 1),
+    (30111, 10),
+    (30109, 137),
+    (30184, 8453),
+    (30110, 42161),
+];
+
+/// One `{address, topics, data}` log recorded by the simulation.
+#[derive(Debug, Clone, Deserialize)]
+struct Log {
+    address: String,
+    #[serde(default)]
+    topics: Vec<String>,
+    #[serde(default)]
+    data: String,
+}
+
+impl Log {
+    fn topic0(&self) -> &str {
+        self.topics.first().map(String::as_str).unwrap_or_default()
+    }
+
+    fn topic_address(&self, index: usize) -> Option<String> {
+        self.topics.get(index).and_then(|topic| word_address(topic))
+    }
+
+    fn word(&self, index: usize) -> Option<u64> {
+        let data = self.data.trim_start_matches("0x");
+        let word = data.get(index * 64..(index + 1) * 64)?;
+        u64::from_str_radix(&word[48..], 16).ok()
+    }
+}
+
+/// An ERC-20 `Transfer(address,address,uint256)` log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transfer {
+    /// Lowercase token contract.
+    pub token: String,
+    pub from: String,
+    pub to: String,
+    pub amount: U256,
+}
+
+/// The Transfer logs among the simulation's JSON-encoded logs, in order.
+pub fn transfers(logs: &[String]) -> Vec<Transfer> {
+    logs.iter()
+        .filter_map(|line| serde_json::from_str::<Log>(line).ok())
+        .filter_map(|log| transfer(&log))
+        .collect()
+}
+
+fn transfer(log: &Log) -> Option<Transfer> {
+    if !log.topic0().eq_ignore_ascii_case(TRANSFER) {
+        return None;
+    }
+    let data = log.data.trim_start_matches("0x");
+    let amount = match data.get(..64.min(data.len())) {
+        Some("") | None => U256::zero(),
+        Some(word) => U256::from_str_radix(word, 16).ok()?,
+    };
+    Some(Transfer {
+        token: log.address.to_ascii_lowercase(),
+        from: log.topic_address(1)?,
+        to: log.topic_address(2)?,
+        amount,
+    })
+}
+
+/// Claimed and executed actions that did not pair up.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventCheck {
+    /// In the route/bundle metadata, but no event shows it happening.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unconfirmed: Vec<ActionSig>,
+    /// Decoded from the logs, but missing from the metadata.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unclaimed: Vec<ActionSig>,
+}
+
+impl EventCheck {
+    pub fn is_empty(&self) -> bool {
+        self.unconfirmed.is_empty() && self.unclaimed.is_empty()
+    }
+}
+
+/// Actions the simulation logs show executing: swaps, lending, vault and bridge events.
+/// Swap tokens come from the Transfer logs into and out of the pool.
+pub fn actions_from_logs(chain_id: u64, logs: &[String]) -> Vec<ActionSig> {
+    let logs: Vec<Log> = logs
+        .iter()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let transfers: Vec<Transfer> = logs.iter().filter_map(transfer).collect();
+    // Token sent to / received from `pool`, if a Transfer shows it.
+    let pair_at = |pool: &str| {
+        let pool = pool.to_ascii_lowercase();
+        let sent_in = transfers.iter().find(|transfer| transfer.to == pool)?;
+        let paid_out = transfers.iter().find(|transfer| transfer.from == pool)?;
+        Some((sent_in.token.clone(), paid_out.token.clone()))
+    };
+    let asset = |token: Option<String>| token.map(|token| (token.clone(), token));
+    let has_bridge_event = logs.iter().any(|log| {
+        [STARGATE_SWAP, OFT_SENT]
+            .iter()
+            .any(|topic| log.topic0().eq_ignore_ascii_case(topic))
+    });
+
+    let mut out = Vec::new();
+    for log in &logs {
+        let sig = |action, protocol: Option<&str>, tokens| {
+            ActionSig::new(chain_id, action, protocol.map(str::to_string), tokens, None)
+        };
+        let bridge = |protocol: &str, destination: Option<u64>| {
+            let destination = destination
+                .and_then(|id| LAYERZERO_CHAINS.iter().find(|(lz, _)| *lz == id))
+                .map(|(_, chain)| *chain)
+                .unwrap_or(chain_id);
+            ActionSig::new(destination, ActionKind::Bridge, Some(protocol.into()), None, None)
+        };
+        let topic0 = log.topic0().to_ascii_lowercase();
+        let decoded = match topic0.as_str() {
+            UNISWAP_V2_SWAP => sig(ActionKind::Swap, Some("uniswap-v2"), pair_at(&log.address)),
+            UNISWAP_V3_SWAP => sig(ActionKind::Swap, Some("uniswap-v3"), pair_at(&log.address)),
+            CURVE_TOKEN_EXCHANGE | CURVE_TOKEN_EXCHANGE_UNDERLYING => {
+                sig(ActionKind::Swap, Some("curve"), pair_at(&log.address))
+            }
+            AAVE_SUPPLY => sig(ActionKind::Deposit, Some("aave-v3"), asset(log.topic_address(1))),
+            AAVE_BORROW => sig(ActionKind::Borrow, Some("aave-v3"), asset(log.topic_address(1))),
+            AAVE_REPAY => sig(ActionKind::Repay, Some("aave-v3"), asset(log.topic_address(1))),
+            AAVE_WITHDRAW => sig(ActionKind::Redeem, Some("aave-v3"), asset(log.topic_address(1))),
+            ERC4626_DEPOSIT => {
+                let vault = log.address.to_ascii_lowercase();
+                let token = transfers
+                    .iter()
+                    .find(|transfer| transfer.to == vault)
+                    .map(|transfer| transfer.token.clone());
+                sig(ActionKind::Deposit, None, asset(token))
+            }
+            ERC4626_WITHDRAW => {
+                let vault = log.address.to_ascii_lowercase();
+                let token = transfers
+                    .iter()
+                    .find(|transfer| transfer.from == vault && transfer.token != vault)
+                    .map(|transfer| transfer.token.clone());
+                sig(ActionKind::Redeem, None, asset(token))
+            }
+            STARGATE_SWAP => bridge("stargate", log.word(0)),
+            OFT_SENT => bridge("layerzero", log.word(0)),
+            PACKET_SENT if !has_bridge_event => bridge("layerzero", None),
+            _ => continue,
+        };
+        out.push(decoded);
+    }
+    out
+}
+
+/// Pairs claimed actions with executed ones by action kind and, when both sides know
+/// them, token pair. Approvals and unknown steps are not checked.
+pub fn cross_check(chain_id: u64, claimed: &[ActionSig], executed: &[ActionSig]) -> EventCheck {
+    let checked = |sig: &&ActionSig| !matches!(sig.action, ActionKind::Approve | ActionKind::Unknown);
+    let mut unmatched: Vec<&ActionSig> = executed.iter().filter(checked).collect();
+    let mut check = EventCheck::default();
+
+    for claim in claimed.iter().filter(checked) {
+        match unmatched
+            .iter()
+            .position(|event| same_action(chain_id, claim, event))
+        {
+            Some(at) => {
+                unmatched.remove(at);
+            }
+            None => check.unconfirmed.push(claim.clone()),
+        }
+    }
+    check.unclaimed = unmatched.into_iter().cloned().collect();
+    check
+}
+
+fn same_action(chain_id: u64, claim: &ActionSig, event: &ActionSig) -> bool {
+    let token = |raw: &String| canonical_token(chain_id, raw);
+    claim.action == event.action
+        && match (&claim.tokens, &event.tokens) {
+            (Some((a_in, a_out)), Some((b_in, b_out))) => {
+                token(a_in) == token(b_in) && token(a_out) == token(b_out)
+            }
+            _ => true,
+        }
+}
+
+/// The address in the low 20 bytes of a 32-byte topic.
+fn word_address(topic: &str) -> Option<String> {
+    let hex = topic.trim_start_matches("0x");
+    (hex.len() == 64).then(|| format!("0x{}", hex[24..].to_ascii_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const POOL: &str = "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640";
+    const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+
+    fn topic(address: &str) -> String {
+        format!("0x000000000000000000000000{}", address.trim_start_matches("0x"))
+    }
+
+    fn log(address: &str, topics: Vec<String>, data: &str) -> String {
+        json!({ "address": address, "topics": topics, "data": data }).to_string()
+    }
+
+    #[test]
+    fn decodes_swaps_and_reports_mismatches() {
+        let wallet = "0x1111111111111111111111111111111111111111";
+        let logs = vec![
+            log(USDC, vec![TRANSFER.into(), topic(wallet), topic(POOL)], "0x"),
+            log(WETH, vec![TRANSFER.into(), topic(POOL), topic(wallet)], "0x"),
+            log(
+                POOL,
+                vec![UNISWAP_V3_SWAP.into(), topic(wallet), topic(wallet)],
+                "0x",
+            ),
+            log(
+                "0x1a13f4ca1d028320a707d99520abfefca3998b7f",
+                vec![OFT_SENT.into(), format!("0x{}", "ab".repeat(32)), topic(wallet)],
+                &format!("0x{:064x}{:064x}{:064x}", 30184, 1, 1),
+            ),
+        ];
+
+        let executed = actions_from_logs(1, &logs);
+        assert_eq!(
+            executed,
+            [
+                ActionSig::new(
+                    1,
+                    ActionKind::Swap,
+                    Some("uniswap-v3".into()),
+                    Some((USDC.into(), WETH.into())),
+                    None
+                ),
+                ActionSig::new(8453, ActionKind::Bridge, Some("layerzero".into()), None, None),
+            ]
+        );
+
+        let claimed = [
+            ActionSig::new(
+                1,
+                ActionKind::Swap,
+                Some("uniswap_v3".into()),
+                Some(("USDC".into(), "WETH".into())),
+                None,
+            ),
+            ActionSig::new(1, ActionKind::Deposit, Some("aave-v3".into()), None, None),
+        ];
+        let check = cross_check(1, &claimed, &executed);
+        assert_eq!(check.unconfirmed, [claimed[1].clone()]);
+        assert_eq!(check.unclaimed, [executed[1].clone()]);
+    }
+}
@@ -0,0 +1,142 @@
+use std::{fs::File, path::Path};
+
+use anyhow::{Context, Result};
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::DomainsConfig, model::ActionSig, parse::load_transactions, score::score};
+
+/// The parts of the runner's experiment.json the evaluator needs.
+#[derive(Debug, Clone, Deserialize)]
+struct Manifest {
+    experiment_id: String,
+    #[serde(default)]
+    model: Option<String>,
+    runs: Vec<ManifestRun>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestRun {
+    repeat: u32,
+    seed: u64,
+    #[serde(default)]
+    run_dir: Option<String>,
+}
+
+/// Score distribution over the repeats of one experiment, as shown on the leaderboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentReport {
+    pub experiment_id: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub runs: Vec<RepeatScore>,
+    /// Repeats that never produced a run folder.
+    pub skipped_repeats: Vec<u32>,
+    pub coverage_score: Distribution,
+    /// Distinct action signatures across all repeats.
+    pub unique_actions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatScore {
+    pub repeat: u32,
+    pub seed: u64,
+    pub run_dir: String,
+    pub final_score: f64,
+    pub unique_action_signatures: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    pub n: usize,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl Distribution {
+    pub fn from_values(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+        Self {
+            n,
+            median,
+            min: sorted[0],
+            max: sorted[n - 1],
+            mean: sorted.iter().sum::<f64>() / n as f64,
+        }
+    }
+}
+
+/// Scores every repeat listed in `<dir>/experiment.json`.
+pub fn score_experiment(dir: &Path, domains: &DomainsConfig) -> Result<ExperimentReport> {
+    let path = dir.join("experiment.json");
+    let file = File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+    let manifest: Manifest = serde_json::from_reader(file)
+        .with_context(|| format!("invalid experiment manifest at {}", path.display()))?;
+
+    let mut runs = Vec::new();
+    let mut skipped_repeats = Vec::new();
+    let mut all_sigs: IndexSet<ActionSig> = IndexSet::new();
+    for run in &manifest.runs {
+        let per_tx = run
+            .run_dir
+            .as_ref()
+            .map(|run_dir| dir.join(run_dir).join("per_tx.jsonl"));
+        let Some(per_tx) = per_tx.filter(|path| path.exists()) else {
+            skipped_repeats.push(run.repeat);
+            continue;
+        };
+        let report = score(&load_transactions(&per_tx)?, domains);
+        all_sigs.extend(report.unique_sigs.values().flatten().cloned());
+        runs.push(RepeatScore {
+            repeat: run.repeat,
+            seed: run.seed,
+            run_dir: run.run_dir.clone().unwrap_or_default(),
+            final_score: report.final_score,
+            unique_action_signatures: report.metadata.unique_action_signatures,
+        });
+    }
+
+    let scores: Vec<f64> = runs.iter().map(|run| run.final_score).collect();
+    Ok(ExperimentReport {
+        experiment_id: manifest.experiment_id,
+        model: manifest.model,
+        coverage_score: Distribution::from_values(&scores),
+        unique_actions: all_sigs.len(),
+        runs,
+        skipped_repeats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarises_scores() {
+        let distribution = Distribution::from_values(&[3.0, 1.0, 4.0, 2.0]);
+        assert_eq!(
+            distribution,
+            Distribution {
+                n: 4,
+                median: 2.5,
+                min: 1.0,
+                max: 4.0,
+                mean: 2.5
+            }
+        );
+        assert_eq!(Distribution::from_values(&[5.0]).median, 5.0);
+        assert_eq!(Distribution::from_values(&[]).n, 0);
+    }
+}
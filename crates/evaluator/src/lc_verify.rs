@@ -1,13 +1,20 @@
-use std::{fs, path::Path};
+use std::{fmt, fs, path::Path};
 
 use anyhow::{Context, Result};
+use indexmap::IndexMap;
 use primitive_types::U256;
 use serde::Deserialize;
 use serde_json::Value;
 
 const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+/// Share mints are Transfers from the zero address.
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
 
-use crate::parse::{ExecutionStatus, ParsedTransaction};
+use crate::{
+    model::{ActionKind, ActionSig},
+    normalize::{canonical_protocol, canonical_token},
+    parse::{ExecutionStatus, ParsedTransaction},
+};
 
 #[derive(Debug, Clone)]
 pub struct LcVerifier {
@@ -19,101 +26,423 @@ impl LcVerifier {
         let path = path.as_ref();
         let data = fs::read_to_string(path)
             .with_context(|| format!("unable to read ground truth at {}", path.display()))?;
-        let truth: GroundTruth = serde_json::from_str(&data)
+        let truth = parse_ground_truth(&data)
             .with_context(|| format!("invalid ground truth JSON at {}", path.display()))?;
         Ok(Self { truth })
     }
 
+    /// Passes when every expected step matches an action, in order, on the step's
+    /// chain and the simulation shows its effect.
     pub fn verify(&self, txs: &[ParsedTransaction]) -> LcVerificationResult {
-        for tx in txs {
-            if tx.envelope.chain_id != self.truth.chain_id {
-                continue;
+        let steps = self.truth.expect.steps();
+        if steps.is_empty() {
+            return LcVerificationResult {
+                pass: false,
+                reason: "ground truth has no expectations".into(),
+            };
+        }
+        // (transaction, action) position the next step may start matching from.
+        let mut next = (0, 0);
+        for (number, step) in steps.iter().enumerate() {
+            match self.find(step, txs, next) {
+                Ok((tx_index, action_index)) => next = (tx_index, action_index + 1),
+                Err(reason) => {
+                    let reason = match steps.len() {
+                        1 => reason,
+                        _ => format!("step {} ({}): {reason}", number + 1, step.kind()),
+                    };
+                    return LcVerificationResult { pass: false, reason };
+                }
             }
+        }
+        let reason = match steps.as_slice() {
+            [step] => format!("matching {} located", step.kind()),
+            steps => format!("all {} steps matched in order", steps.len()),
+        };
+        LcVerificationResult { pass: true, reason }
+    }
 
-            let mut tokens_match = false;
-            for sig in &tx.actions {
-                if let Some((token_in, token_out)) = &sig.tokens {
-                    if token_in.eq_ignore_ascii_case(&self.truth.token_in)
-                        && token_out.eq_ignore_ascii_case(&self.truth.token_out)
-                    {
-                        tokens_match = true;
-                        break;
+    /// First action at or after `from` that satisfies `step`, or why none did.
+    fn find(
+        &self,
+        step: &Expectation,
+        txs: &[ParsedTransaction],
+        from: (usize, usize),
+    ) -> Result<(usize, usize), String> {
+        let chain_id = step.chain_id().unwrap_or(self.truth.chain_id);
+        let mut failure = None;
+        for (tx_index, tx) in txs.iter().enumerate().skip(from.0) {
+            let first = if tx_index == from.0 { from.1 } else { 0 };
+            for (action_index, sig) in tx.actions.iter().enumerate().skip(first) {
+                // A bridge leaves the tx's chain; its signature carries the destination.
+                let runs_on = match sig.action {
+                    ActionKind::Bridge => tx.envelope.chain_id,
+                    _ => sig.chain_id,
+                };
+                if runs_on != chain_id || !step.describes(chain_id, sig) {
+                    continue;
+                }
+                match self.check(step, tx, sig) {
+                    Ok(()) => return Ok((tx_index, action_index)),
+                    Err(reason) => {
+                        failure.get_or_insert(reason);
                     }
                 }
             }
+        }
+        Err(failure.unwrap_or_else(|| "no transaction matched ground truth".into()))
+    }
 
-            if !tokens_match {
-                continue;
+    /// Recipient, simulation outcome and the step's own effect checks.
+    fn check(&self, step: &Expectation, tx: &ParsedTransaction, sig: &ActionSig) -> Result<(), String> {
+        let recipient = step.recipient().or(self.truth.recipient.clone());
+        if let Some(expected) = &recipient {
+            if !recipient_matches(tx, expected) {
+                return Err(format!("no matching recipient found (expected {expected})"));
             }
-
-            if let Some(expected_recipient) = &self.truth.recipient {
-                let recipient_match = tx
-                    .request_recipient
-                    .as_ref()
-                    .map(|r| r.eq_ignore_ascii_case(expected_recipient))
-                    .unwrap_or(false)
-                    || tx.actions.iter().any(|sig| {
-                        sig.recipient
-                            .as_ref()
-                            .map(|r| r.eq_ignore_ascii_case(expected_recipient))
-                            .unwrap_or(false)
-                    });
-
-                if !recipient_match {
-                    return LcVerificationResult {
-                        pass: false,
-                        reason: format!("no matching recipient found (expected {})", expected_recipient),
-                    };
-                }
+        }
+        match tx.execution_status {
+            ExecutionStatus::Revert => return Err("transaction reverted during simulation".into()),
+            ExecutionStatus::Skipped if step.checks_effects() => {
+                return Err("transaction was not simulated, so its effects cannot be verified".into())
             }
-
-            if matches!(tx.execution_status, ExecutionStatus::Revert) {
-                return LcVerificationResult {
-                    pass: false,
-                    reason: "transaction reverted during simulation".into(),
-                };
+            _ => {}
+        }
+        if let Some(expected) = step.protocol() {
+            let aliases = IndexMap::new();
+            let actual = sig.protocol.as_deref().unwrap_or_default();
+            if canonical_protocol(expected, &aliases) != canonical_protocol(actual, &aliases) {
+                let actual = sig.protocol.as_deref().unwrap_or("none");
+                return Err(format!("expected protocol {expected}, got {actual}"));
             }
+        }
 
-            if let Some(required_min_out) = &self.truth.min_out {
-                match check_min_out(required_min_out, &self.truth.token_out, &self.truth.recipient, tx) {
-                    Ok(true) => {}
-                    Ok(false) => {
-                        return LcVerificationResult {
-                            pass: false,
-                            reason: format!("min_out not satisfied (expected >= {})", required_min_out),
-                        };
+        match step {
+            Expectation::Swap {
+                token_out,
+                min_out_wei: Some(required),
+                ..
+            } => match check_min_out(required, token_out, &recipient, tx) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(format!("min_out not satisfied (expected >= {required})")),
+                Err(err) => Err(format!("unable to verify min_out: {err}")),
+            },
+            Expectation::Bridge {
+                token,
+                amount_wei,
+                gas_drop_wei,
+                ..
+            } => {
+                if let Some(token) = token {
+                    let sent = largest_transfer(tx, token, None, None)?;
+                    let required = amount_wei.as_deref().map(parse_decimal).transpose()?;
+                    match required {
+                        _ if sent.is_zero() => {
+                            return Err(format!("no {token} transfer in the bridge transaction"))
+                        }
+                        Some(required) if sent < required => {
+                            return Err(format!("bridged {sent} {token} (expected >= {required})"))
+                        }
+                        _ => {}
                     }
-                    Err(err) => {
-                        return LcVerificationResult {
-                            pass: false,
-                            reason: format!("unable to verify min_out: {}", err),
-                        };
+                } else if amount_wei.is_some() {
+                    return Err("bridge amount_wei needs a token to check".into());
+                }
+                if let Some(required) = gas_drop_wei {
+                    // The destination gas drop is paid for out of the transaction value.
+                    let value = parse_amount(&tx.envelope.value)?;
+                    if value < parse_decimal(required)? {
+                        return Err(format!(
+                            "tx value {value} does not cover the {required} wei gas drop"
+                        ));
                     }
                 }
+                Ok(())
             }
-
-            return LcVerificationResult {
-                pass: true,
-                reason: "matching swap located".into(),
-            };
+            Expectation::Deposit {
+                share_token: Some(share_token),
+                min_shares,
+                ..
+            } => {
+                let minted = largest_transfer(tx, share_token, Some(ZERO_ADDRESS), recipient.as_deref())?;
+                let required = min_shares.as_deref().map(parse_decimal).transpose()?;
+                match required {
+                    _ if minted.is_zero() => Err(format!("no {share_token} shares minted")),
+                    Some(required) if minted < required => Err(format!(
+                        "{minted} {share_token} shares minted (expected >= {required})"
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            Expectation::Borrow {
+                asset,
+                min_amount_wei: Some(required),
+                ..
+            } => {
+                let borrowed = largest_transfer(tx, asset, None, recipient.as_deref())?;
+                if borrowed < parse_decimal(required)? {
+                    return Err(format!("borrowed {borrowed} {asset} (expected >= {required})"));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
         }
+    }
+}
 
-        LcVerificationResult {
-            pass: false,
-            reason: "no transaction matched ground truth".into(),
-        }
+/// Legacy files carry swap fields at the top level; v2 files nest a tagged `expect`.
+fn parse_ground_truth(data: &str) -> Result<GroundTruth> {
+    let value: Value = serde_json::from_str(data)?;
+    if value.get("expect").is_some() {
+        return Ok(serde_json::from_value(value)?);
     }
+    let legacy: LegacyGroundTruth = serde_json::from_value(value)?;
+    Ok(GroundTruth {
+        chain_id: legacy.chain_id,
+        recipient: legacy.recipient,
+        expect: Expectation::Swap {
+            chain_id: None,
+            token_in: TokenRef::Plain(legacy.token_in),
+            token_out: TokenRef::Plain(legacy.token_out),
+            protocol: None,
+            min_out_wei: legacy.min_out,
+            recipient: None,
+        },
+    })
 }
 
+/// ground_truth.json, see TECH_PLAN §3.1. Other keys (`case_id`, ...) are ignored.
 #[derive(Debug, Clone, Deserialize)]
 struct GroundTruth {
+    /// Chain the expected transactions are sent on.
     pub chain_id: u64,
-    pub token_in: String,
-    pub token_out: String,
-    #[serde(default)]
-    pub min_out: Option<String>,
+    /// Default recipient for steps that do not name one.
     #[serde(default)]
     pub recipient: Option<String>,
+    pub expect: Expectation,
+}
+
+/// The pre-`expect` format: one swap described by top-level fields.
+#[derive(Debug, Clone, Deserialize)]
+struct LegacyGroundTruth {
+    chain_id: u64,
+    token_in: String,
+    token_out: String,
+    #[serde(default)]
+    min_out: Option<String>,
+    #[serde(default)]
+    recipient: Option<String>,
+}
+
+/// What the agent's transactions must do. Amounts are decimal strings in base units.
+/// Each single step may name the `chain_id` its action runs on (the chain a bridge
+/// leaves); it defaults to the ground truth's.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Expectation {
+    Swap {
+        #[serde(default)]
+        chain_id: Option<u64>,
+        token_in: TokenRef,
+        token_out: TokenRef,
+        #[serde(default)]
+        protocol: Option<String>,
+        /// Least `token_out` the recipient must receive in a single Transfer.
+        #[serde(default, alias = "min_out")]
+        min_out_wei: Option<String>,
+        #[serde(default)]
+        recipient: Option<String>,
+    },
+    Bridge {
+        #[serde(default)]
+        chain_id: Option<u64>,
+        /// Token leaving the source chain; checked against the Transfer logs.
+        #[serde(default)]
+        token: Option<TokenRef>,
+        /// Least `token` that must leave in a single Transfer.
+        #[serde(default, alias = "amount")]
+        amount_wei: Option<String>,
+        destination_chain: u64,
+        #[serde(default)]
+        protocol: Option<String>,
+        #[serde(default)]
+        recipient: Option<String>,
+        /// Native gas delivered on the destination chain, paid via the tx value.
+        #[serde(default)]
+        gas_drop_wei: Option<String>,
+    },
+    Deposit {
+        #[serde(default)]
+        chain_id: Option<u64>,
+        asset: TokenRef,
+        #[serde(default)]
+        protocol: Option<String>,
+        /// Vault or receipt token that must be minted to the recipient.
+        #[serde(default)]
+        share_token: Option<TokenRef>,
+        #[serde(default)]
+        min_shares: Option<String>,
+        #[serde(default)]
+        recipient: Option<String>,
+    },
+    Borrow {
+        #[serde(default)]
+        chain_id: Option<u64>,
+        asset: TokenRef,
+        #[serde(default)]
+        protocol: Option<String>,
+        /// Least `asset` the recipient must receive in a single Transfer.
+        #[serde(default)]
+        min_amount_wei: Option<String>,
+        #[serde(default)]
+        recipient: Option<String>,
+    },
+    /// Steps that must match in this order, within one transaction or across several.
+    MultiStep { steps: Vec<Expectation> },
+}
+
+impl Expectation {
+    fn kind(&self) -> &'static str {
+        match self {
+            Expectation::Swap { .. } => "swap",
+            Expectation::Bridge { .. } => "bridge",
+            Expectation::Deposit { .. } => "deposit",
+            Expectation::Borrow { .. } => "borrow",
+            Expectation::MultiStep { .. } => "multi_step",
+        }
+    }
+
+    /// The single steps, with nested `multi_step`s flattened in order.
+    fn steps(&self) -> Vec<&Expectation> {
+        match self {
+            Expectation::MultiStep { steps } => steps.iter().flat_map(Expectation::steps).collect(),
+            step => vec![step],
+        }
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        match self {
+            Expectation::Swap { chain_id, .. }
+            | Expectation::Bridge { chain_id, .. }
+            | Expectation::Deposit { chain_id, .. }
+            | Expectation::Borrow { chain_id, .. } => *chain_id,
+            Expectation::MultiStep { .. } => None,
+        }
+    }
+
+    /// Whether the step checks amounts or effects only a simulation can show.
+    fn checks_effects(&self) -> bool {
+        match self {
+            Expectation::Swap { min_out_wei, .. } => min_out_wei.is_some(),
+            Expectation::Bridge {
+                token,
+                amount_wei,
+                gas_drop_wei,
+                ..
+            } => token.is_some() || amount_wei.is_some() || gas_drop_wei.is_some(),
+            Expectation::Deposit {
+                share_token,
+                min_shares,
+                ..
+            } => share_token.is_some() || min_shares.is_some(),
+            Expectation::Borrow { min_amount_wei, .. } => min_amount_wei.is_some(),
+            Expectation::MultiStep { .. } => false,
+        }
+    }
+
+    fn recipient(&self) -> Option<String> {
+        match self {
+            Expectation::Swap { recipient, .. }
+            | Expectation::Bridge { recipient, .. }
+            | Expectation::Deposit { recipient, .. }
+            | Expectation::Borrow { recipient, .. } => recipient.clone(),
+            Expectation::MultiStep { .. } => None,
+        }
+    }
+
+    fn protocol(&self) -> Option<&str> {
+        match self {
+            Expectation::Swap { protocol, .. }
+            | Expectation::Bridge { protocol, .. }
+            | Expectation::Deposit { protocol, .. }
+            | Expectation::Borrow { protocol, .. } => protocol.as_deref(),
+            Expectation::MultiStep { .. } => None,
+        }
+    }
+
+    /// Whether `sig` is the kind of action this step asks for, with the right tokens
+    /// (or destination chain, for bridges).
+    fn describes(&self, chain_id: u64, sig: &ActionSig) -> bool {
+        let tokens = |a: &TokenRef, b: &TokenRef| {
+            sig.tokens
+                .as_ref()
+                .is_some_and(|(x, y)| a.matches(chain_id, x) && b.matches(chain_id, y))
+        };
+        match self {
+            Expectation::Swap {
+                token_in, token_out, ..
+            } => matches!(sig.action, ActionKind::Swap) && tokens(token_in, token_out),
+            Expectation::Bridge {
+                destination_chain, ..
+            } => matches!(sig.action, ActionKind::Bridge) && sig.chain_id == *destination_chain,
+            Expectation::Deposit { asset, .. } => {
+                matches!(sig.action, ActionKind::Deposit) && tokens(asset, asset)
+            }
+            Expectation::Borrow { asset, .. } => {
+                matches!(sig.action, ActionKind::Borrow) && tokens(asset, asset)
+            }
+            Expectation::MultiStep { .. } => false,
+        }
+    }
+}
+
+/// A token as an address or symbol string, or as `{symbol, address}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum TokenRef {
+    Plain(String),
+    Detailed {
+        #[serde(default)]
+        symbol: Option<String>,
+        #[serde(default)]
+        address: Option<String>,
+    },
+}
+
+impl TokenRef {
+    /// Compares canonical spellings, so an address also matches its known symbol.
+    fn matches(&self, chain_id: u64, raw: &str) -> bool {
+        let raw = canonical_token(chain_id, raw);
+        let spellings = match self {
+            TokenRef::Plain(token) => vec![token],
+            TokenRef::Detailed { symbol, address } => address.iter().chain(symbol).collect(),
+        };
+        spellings
+            .into_iter()
+            .any(|token| canonical_token(chain_id, token) == raw)
+    }
+}
+
+impl fmt::Display for TokenRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenRef::Plain(token) => f.write_str(token),
+            TokenRef::Detailed { symbol, address } => {
+                f.write_str(symbol.as_deref().or(address.as_deref()).unwrap_or("?"))
+            }
+        }
+    }
+}
+
+fn recipient_matches(tx: &ParsedTransaction, expected: &str) -> bool {
+    tx.request_recipient
+        .as_ref()
+        .is_some_and(|r| r.eq_ignore_ascii_case(expected))
+        || tx.actions.iter().any(|sig| {
+            sig.recipient
+                .as_ref()
+                .is_some_and(|r| r.eq_ignore_ascii_case(expected))
+        })
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -124,7 +453,7 @@ pub struct LcVerificationResult {
 
 fn check_min_out(
     required: &str,
-    token_out: &str,
+    token_out: &TokenRef,
     recipient: &Option<String>,
     tx: &ParsedTransaction,
 ) -> Result<bool, String> {
@@ -132,22 +461,28 @@ fn check_min_out(
     let Some(recipient_expected) = recipient.as_ref() else {
         return Err("ground truth missing recipient for min_out check".into());
     };
+    Ok(largest_transfer(tx, token_out, None, Some(recipient_expected))? >= expected)
+}
 
+/// Largest single `token` Transfer in the simulation logs, optionally restricted to a
+/// sender and/or receiver.
+fn largest_transfer(
+    tx: &ParsedTransaction,
+    token: &TokenRef,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<U256, String> {
     let mut observed = U256::zero();
-    let token_out_clean = normalize_addr(token_out);
-    let recipient_clean = normalize_addr(recipient_expected);
+    let from_clean = from.map(normalize_addr);
+    let to_clean = to.map(normalize_addr);
 
     for log_str in &tx.execution_logs {
         let Ok(value) = serde_json::from_str::<Value>(log_str) else {
             continue;
         };
 
-        let address = value
-            .get("address")
-            .and_then(|v| v.as_str())
-            .map(normalize_addr)
-            .unwrap_or_default();
-        if address != token_out_clean {
+        let address = value.get("address").and_then(|v| v.as_str()).unwrap_or_default();
+        if !token.matches(tx.envelope.chain_id, address) {
             continue;
         }
 
@@ -162,8 +497,12 @@ fn check_min_out(
             continue;
         }
 
-        let topic2 = topics[2].as_str().unwrap_or_default();
-        if normalize_addr(topic2) != recipient_clean {
+        let topic_matches = |index: usize, expected: &Option<String>| {
+            expected.as_ref().is_none_or(|expected| {
+                normalize_addr(topics[index].as_str().unwrap_or_default()) == *expected
+            })
+        };
+        if !topic_matches(1, &from_clean) || !topic_matches(2, &to_clean) {
             continue;
         }
 
@@ -174,7 +513,16 @@ fn check_min_out(
         }
     }
 
-    Ok(observed >= expected)
+    Ok(observed)
+}
+
+/// A `0x` hex or decimal quantity, as transaction values are written.
+fn parse_amount(value: &str) -> Result<U256, String> {
+    if value.starts_with("0x") {
+        parse_hex(value)
+    } else {
+        parse_decimal(value)
+    }
 }
 
 fn parse_decimal(value: &str) -> Result<U256, String> {
@@ -234,7 +582,11 @@ mod tests {
             )],
             execution_status: ExecutionStatus::Success,
             execution_logs: logs,
+            transaction_hash: None,
+            executed_actions: Vec::new(),
+            intent_id: None,
             request_recipient: Some("0x1111111111111111111111111111111111111111".into()),
+            policy_violations: Vec::new(),
         }
     }
 
@@ -242,10 +594,11 @@ mod tests {
     fn min_out_satisfied() {
         let recipient = "0x1111111111111111111111111111111111111111";
         let token_out = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
-        let amount_hex = "0x0000000000000000000000000000000000000000000000000056bc75e2d631000"; // 0.1 ether
+        let amount_hex = "0x000000000000000000000000000000000000000000000000016345785d8a0000"; // 0.1 ether
         let tx = sample_transaction(vec![make_log(token_out, recipient, amount_hex)]);
 
-        let result = check_min_out("100000000000000000", token_out, &Some(recipient.into()), &tx)
+        let token_out = TokenRef::Plain(token_out.into());
+        let result = check_min_out("100000000000000000", &token_out, &Some(recipient.into()), &tx)
             .expect("min_out check should succeed");
         assert!(result);
     }
@@ -257,8 +610,156 @@ mod tests {
         let amount_hex = "0x0000000000000000000000000000000000000000000000000000000000002710"; // 10_000 wei
         let tx = sample_transaction(vec![make_log(token_out, recipient, amount_hex)]);
 
-        let result = check_min_out("100000000000000000", token_out, &Some(recipient.into()), &tx)
+        let token_out = TokenRef::Plain(token_out.into());
+        let result = check_min_out("100000000000000000", &token_out, &Some(recipient.into()), &tx)
             .expect("min_out check should succeed");
         assert!(!result);
     }
+
+    #[test]
+    fn reads_legacy_and_v2_ground_truth() {
+        let legacy = r#"{
+            "chain_id": 1,
+            "token_in": "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+            "token_out": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+            "amount": "100000000",
+            "recipient": "0x1111111111111111111111111111111111111111",
+            "min_out": "50000000000000000"
+        }"#;
+        let v2 = include_str!("../../../dataset/lc/swap_usdc_weth/ground_truth.json");
+        let amount_hex = "0x000000000000000000000000000000000000000000000000016345785d8a0000";
+        let tx = sample_transaction(vec![make_log(
+            "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+            "0x1111111111111111111111111111111111111111",
+            amount_hex,
+        )]);
+        for data in [legacy, v2] {
+            let verifier = LcVerifier {
+                truth: parse_ground_truth(data).expect("parse ground truth"),
+            };
+            let result = verifier.verify(std::slice::from_ref(&tx));
+            assert!(result.pass, "{}", result.reason);
+            assert_eq!(result.reason, "matching swap located");
+        }
+        parse_ground_truth(include_str!(
+            "../../../dataset/lc/bridge_usdc_op/ground_truth.json"
+        ))
+        .expect("parse bridge ground truth");
+    }
+
+    #[test]
+    fn verifies_typed_steps_in_order() {
+        let recipient = "0x1111111111111111111111111111111111111111";
+        let a_weth = "0x4d5f47fa6a74757f35c14fd3a6ef8e3c9bc514e8";
+        let mut tx = sample_transaction(vec![make_log(a_weth, recipient, "0x2710")]);
+        tx.actions.push(ActionSig::new(
+            1,
+            ActionKind::Deposit,
+            Some("aave_v3".into()),
+            Some(("WETH".into(), "WETH".into())),
+            None,
+        ));
+        tx.actions.push(ActionSig::new(
+            10,
+            ActionKind::Bridge,
+            Some("stargate".into()),
+            None,
+            Some(recipient.into()),
+        ));
+        let verify = |expect: Value| {
+            let truth = serde_json::json!({ "chain_id": 1, "recipient": recipient, "expect": expect });
+            let truth = parse_ground_truth(&truth.to_string()).expect("parse ground truth");
+            LcVerifier { truth }.verify(std::slice::from_ref(&tx))
+        };
+        let swap = serde_json::json!({ "type": "swap", "token_in": "USDC", "token_out": "WETH" });
+        let deposit = serde_json::json!({
+            "type": "deposit",
+            "asset": { "symbol": "WETH" },
+            "protocol": "aave-v3",
+            "share_token": a_weth,
+        });
+        let bridge = serde_json::json!({ "type": "bridge", "destination_chain": 10, "protocol": "stargate" });
+
+        let ordered = verify(serde_json::json!({ "type": "multi_step", "steps": [swap, deposit, bridge] }));
+        assert!(ordered.pass, "{}", ordered.reason);
+        assert_eq!(ordered.reason, "all 3 steps matched in order");
+
+        let reversed = verify(serde_json::json!({ "type": "multi_step", "steps": [deposit, swap] }));
+        assert!(!reversed.pass);
+        assert_eq!(
+            reversed.reason,
+            "step 2 (swap): no transaction matched ground truth"
+        );
+
+        let gas_drop = verify(serde_json::json!({
+            "type": "bridge",
+            "destination_chain": 10,
+            "gas_drop_wei": "1000",
+        }));
+        assert_eq!(gas_drop.reason, "tx value 0 does not cover the 1000 wei gas drop");
+
+        let wrong_share = verify(serde_json::json!({
+            "type": "deposit",
+            "asset": "WETH",
+            "share_token": "0x0000000000000000000000000000000000000bad",
+        }));
+        assert!(!wrong_share.pass);
+        let no_borrow = verify(serde_json::json!({ "type": "borrow", "asset": "USDC" }));
+        assert_eq!(no_borrow.reason, "no transaction matched ground truth");
+    }
+
+    #[test]
+    fn matches_steps_on_their_own_chain() {
+        let recipient = "0x1111111111111111111111111111111111111111";
+        let usdc_e = "0xFF970A61A04b1cA14834A43f5dE4533eBDDB5CC8";
+        // 25 USDC.e leaving Arbitrum, then a deposit on Optimism in the same bundle.
+        let mut tx = sample_transaction(vec![make_log(usdc_e, recipient, "0x17d7840")]);
+        tx.envelope.chain_id = 42161;
+        tx.actions = vec![
+            ActionSig::new(10, ActionKind::Bridge, Some("stargate".into()), None, None),
+            ActionSig::new(
+                10,
+                ActionKind::Deposit,
+                Some("aave-v3".into()),
+                Some(("USDC".into(), "USDC".into())),
+                None,
+            ),
+        ];
+        let verify = |tx: &ParsedTransaction, expect: Value| {
+            let truth = serde_json::json!({ "chain_id": 42161, "recipient": recipient, "expect": expect });
+            let truth = parse_ground_truth(&truth.to_string()).expect("parse ground truth");
+            LcVerifier { truth }.verify(std::slice::from_ref(tx))
+        };
+        let bridge = |amount: &str| {
+            serde_json::json!({
+                "type": "bridge",
+                "token": usdc_e,
+                "amount": amount,
+                "destination_chain": 10,
+            })
+        };
+        let deposit = serde_json::json!({ "type": "deposit", "chain_id": 10, "asset": "USDC" });
+
+        let steps = serde_json::json!({ "type": "multi_step", "steps": [bridge("25000000"), deposit] });
+        let result = verify(&tx, steps.clone());
+        assert!(result.pass, "{}", result.reason);
+
+        let source_deposit = serde_json::json!({ "type": "deposit", "asset": "USDC" });
+        assert_eq!(
+            verify(&tx, source_deposit).reason,
+            "no transaction matched ground truth"
+        );
+        assert_eq!(
+            verify(&tx, bridge("30000000")).reason,
+            "bridged 25000000 0xFF970A61A04b1cA14834A43f5dE4533eBDDB5CC8 (expected >= 30000000)"
+        );
+
+        tx.execution_status = ExecutionStatus::Skipped;
+        assert_eq!(
+            verify(&tx, steps).reason,
+            "step 1 (bridge): transaction was not simulated, so its effects cannot be verified"
+        );
+        let no_effects = serde_json::json!({ "type": "bridge", "destination_chain": 10 });
+        assert!(verify(&tx, no_effects).pass);
+    }
 }
@@ -1,11 +1,22 @@
+pub mod calldata;
 pub mod cli;
 pub mod config;
+pub mod diff;
+pub mod events;
+pub mod experiment;
 pub mod lc_verify;
 pub mod model;
+pub mod noop;
+pub mod normalize;
 pub mod parse;
 pub mod score;
 
 pub use cli::EvaluatorCli;
 pub use config::DomainsConfig;
+pub use diff::RunDiff;
+pub use experiment::{Distribution, ExperimentReport};
 pub use lc_verify::{LcVerificationResult, LcVerifier};
-pub use model::{ActionKind, ActionSig, ScoreReport};
+pub use model::{
+    ActionKind, ActionSig, BonusItem, BonusKind, CoverageGaps, PenaltyItem, PenaltyKind, ScoreReport,
+    TxExplanation,
+};
@@ -3,6 +3,8 @@ use std::{fmt, str::FromStr};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
+use crate::{calldata::CalldataCheck, events::EventCheck};
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ActionKind {
@@ -87,8 +89,13 @@ pub struct ScoreReport {
     pub by_domain: IndexMap<String, f64>,
     pub bonus: f64,
     pub penalty: f64,
+    /// The deductions that make up `penalty`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub penalties: Vec<PenaltyItem>,
     pub unique_sigs: IndexMap<String, Vec<ActionSig>>,
     pub metadata: ScoreMeta,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub efficiency: Option<Efficiency>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,3 +103,121 @@ pub struct ScoreMeta {
     pub total_transactions: usize,
     pub unique_action_signatures: usize,
 }
+
+/// One bonus a transaction earned, see [`BonusConfig`](crate::config::BonusConfig).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BonusItem {
+    pub kind: BonusKind,
+    pub amount: f64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BonusKind {
+    Composition,
+    CrossChain,
+    MultiProtocol,
+    DomainDiversity,
+}
+
+/// One deduction behind [`ScoreReport::penalty`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PenaltyItem {
+    pub kind: PenaltyKind,
+    /// Transaction the deduction was charged to (same numbering as [`TxExplanation::index`]).
+    pub tx_index: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sig: Option<ActionSig>,
+    pub amount: f64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyKind {
+    PolicyViolation,
+    Cooldown,
+    ProtocolCap,
+    NearDuplicate,
+}
+
+/// How one per_tx line contributed to the score; also the line format of
+/// eval_per_tx.jsonl. Summing `score` over a run gives its `final_score`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxExplanation {
+    /// The per_tx line's `intent_id`, or `tx-<index>` when it has none.
+    pub intent_id: String,
+    /// Position among the run's transactions (plan failures are not counted).
+    pub index: usize,
+    pub chain_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    /// Distinct domains of the extracted actions, in order of appearance.
+    pub domains: Vec<String>,
+    /// Why the transaction earned no coverage, e.g. `revert`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignored: Option<String>,
+    pub actions: Vec<ActionExplanation>,
+    pub bonus: f64,
+    /// The bonuses that make up `bonus`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bonuses: Vec<BonusItem>,
+    pub penalty: f64,
+    pub score: f64,
+    /// Metadata actions the simulation logs do not confirm, and vice versa. Only
+    /// checked for transactions that have logs.
+    #[serde(default, skip_serializing_if = "EventCheck::is_empty")]
+    pub event_check: EventCheck,
+    /// Decoded router/delegate calldata and the `tx.to` check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calldata: Option<CalldataCheck>,
+    /// Human-readable remarks, e.g. the policy violations behind `penalty`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// Whether an extracted signature counted, in which domain, and why.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionExplanation {
+    #[serde(flatten)]
+    pub sig: ActionSig,
+    pub domain: String,
+    pub counted: bool,
+    pub points: f64,
+    pub reason: String,
+}
+
+/// Resource usage of the run that produced the artifacts, and score per unit spent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Efficiency {
+    pub wall_clock_seconds: f64,
+    pub score_per_second: Option<f64>,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub llm_calls: u32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub llm_latency_seconds: f64,
+    pub cost_usd: Option<f64>,
+    pub score_per_dollar: Option<f64>,
+}
+
+/// What a run has not covered yet, relative to a [`DomainsConfig`](crate::DomainsConfig).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoverageGaps {
+    pub uncovered_domains: Vec<String>,
+    pub uncovered_actions: IndexMap<String, Vec<ActionKind>>,
+    pub uncovered_protocols: IndexMap<String, Vec<String>>,
+    pub uncovered_chains: Vec<u64>,
+}
+
+impl CoverageGaps {
+    pub fn is_empty(&self) -> bool {
+        self.uncovered_domains.is_empty()
+            && self.uncovered_actions.is_empty()
+            && self.uncovered_protocols.is_empty()
+            && self.uncovered_chains.is_empty()
+    }
+}
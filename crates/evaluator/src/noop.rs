@@ -0,0 +1,198 @@
+use indexmap::{IndexMap, IndexSet};
+use primitive_types::U256;
+
+use crate::{
+    events::transfers,
+    model::{ActionKind, ActionSig},
+    normalize::{canonical_token, is_known_token},
+    parse::{ExecutionStatus, ParsedTransaction},
+};
+
+/// Native ether placeholder used by Enso and most aggregators.
+const NATIVE_TOKEN: &str = "0xeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee";
+
+/// Whether a transaction had an effect worth scoring.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    Effective,
+    Reverted,
+    Unsimulated,
+    /// Succeeded but changed nothing the claimed actions imply; carries the reason.
+    NoOp(String),
+}
+
+impl Effect {
+    /// Value of `TxExplanation::ignored` for transactions left out of scoring.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Effect::Effective => "effective",
+            Effect::Reverted => "revert",
+            Effect::Unsimulated => "unsimulated",
+            Effect::NoOp(_) => "no_op",
+        }
+    }
+
+    /// Reason shown next to each action of an excluded transaction.
+    pub fn reason(&self) -> String {
+        match self {
+            Effect::Effective => String::new(),
+            Effect::Reverted => "transaction reverted".into(),
+            Effect::Unsimulated => "transaction was not simulated".into(),
+            Effect::NoOp(why) => format!("no effect: {why}"),
+        }
+    }
+}
+
+/// Classifies a transaction from its execution status, logs, the net token balance
+/// changes its Transfer logs imply and the tokens its claimed actions should move.
+/// Approval-only transactions count as effective once they emit any log.
+pub fn classify(tx: &ParsedTransaction) -> Effect {
+    match tx.execution_status {
+        ExecutionStatus::Revert => return Effect::Reverted,
+        ExecutionStatus::Skipped => return Effect::Unsimulated,
+        ExecutionStatus::Success => {}
+    }
+    let chain_id = tx.envelope.chain_id;
+    let sends_value = !is_zero_value(&tx.envelope.value);
+    if tx.execution_logs.is_empty() {
+        return if sends_value {
+            Effect::Effective
+        } else {
+            Effect::NoOp("no logs emitted".into())
+        };
+    }
+
+    let moving: Vec<&ActionSig> = tx
+        .actions
+        .iter()
+        .filter(|action| !matches!(action.action, ActionKind::Approve | ActionKind::Unknown))
+        .collect();
+    if moving.is_empty() {
+        return Effect::Effective;
+    }
+
+    let transfers = transfers(&tx.execution_logs);
+    if transfers.iter().all(|transfer| transfer.amount.is_zero()) {
+        return if sends_value || !tx.executed_actions.is_empty() {
+            Effect::Effective
+        } else {
+            Effect::NoOp("no tokens moved".into())
+        };
+    }
+
+    // Net balance change per (token, holder); a token moved if any holder's changed.
+    let mut balances: IndexMap<(&str, &str), (U256, U256)> = IndexMap::new();
+    for transfer in &transfers {
+        balances.entry((&transfer.token, &transfer.from)).or_default().0 += transfer.amount;
+        balances.entry((&transfer.token, &transfer.to)).or_default().1 += transfer.amount;
+    }
+    let moved: IndexSet<String> = balances
+        .iter()
+        .filter(|(_, (out, into))| out != into)
+        .map(|((token, _), _)| canonical_token(chain_id, token))
+        .collect();
+    if moved.is_empty() {
+        return Effect::NoOp("token balances unchanged".into());
+    }
+
+    // Only tokens a Transfer log can show: addresses and known symbols, not native ether.
+    let claimed: IndexSet<String> = moving
+        .iter()
+        .filter_map(|action| action.tokens.as_ref())
+        .flat_map(|(a, b)| [a, b])
+        .filter(|token| !token.eq_ignore_ascii_case(NATIVE_TOKEN))
+        .map(|token| canonical_token(chain_id, token))
+        .filter(|token| token.starts_with("0x") || is_known_token(chain_id, token))
+        .collect();
+    if !claimed.is_empty() && claimed.is_disjoint(&moved) {
+        return Effect::NoOp("none of the claimed tokens moved".into());
+    }
+    Effect::Effective
+}
+
+fn is_zero_value(value: &str) -> bool {
+    let digits = value.trim().trim_start_matches("0x");
+    digits.chars().all(|c| c == '0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::TransactionEnvelope;
+
+    const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    const TRANSFER: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+    fn transfer(token: &str, from: &str, to: &str, amount: u64) -> String {
+        let topic = |address: &str| format!("0x{:0>64}", address.trim_start_matches("0x"));
+        serde_json::json!({
+            "address": token,
+            "topics": [TRANSFER, topic(from), topic(to)],
+            "data": format!("0x{amount:064x}"),
+        })
+        .to_string()
+    }
+
+    fn swap(status: ExecutionStatus, logs: Vec<String>) -> ParsedTransaction {
+        ParsedTransaction {
+            envelope: TransactionEnvelope {
+                to: "0x0".into(),
+                data: "0x".into(),
+                value: "0x0".into(),
+                chain_id: 1,
+            },
+            actions: vec![ActionSig::new(
+                1,
+                ActionKind::Swap,
+                Some("uniswap-v3".into()),
+                Some(("USDC".into(), "WETH".into())),
+                None,
+            )],
+            execution_status: status,
+            transaction_hash: None,
+            executed_actions: Vec::new(),
+            execution_logs: logs,
+            request_recipient: None,
+            policy_violations: Vec::new(),
+            intent_id: None,
+        }
+    }
+
+    #[test]
+    fn separates_effective_from_no_op() {
+        let (user, pool) = ("0x1111", "0x2222");
+        let effective = swap(
+            ExecutionStatus::Success,
+            vec![transfer(USDC, user, pool, 100), transfer(WETH, pool, user, 1)],
+        );
+        assert_eq!(classify(&effective), Effect::Effective);
+
+        assert_eq!(
+            classify(&swap(ExecutionStatus::Revert, Vec::new())),
+            Effect::Reverted
+        );
+        assert_eq!(
+            classify(&swap(ExecutionStatus::Skipped, Vec::new())),
+            Effect::Unsimulated
+        );
+        assert_eq!(
+            classify(&swap(ExecutionStatus::Success, Vec::new())),
+            Effect::NoOp("no logs emitted".into())
+        );
+        let round_trip = swap(
+            ExecutionStatus::Success,
+            vec![transfer(USDC, user, pool, 100), transfer(USDC, pool, user, 100)],
+        );
+        assert_eq!(
+            classify(&round_trip),
+            Effect::NoOp("token balances unchanged".into())
+        );
+        let dai = "0x6b175474e89094c44da98b954eedeac495271d0f";
+        let unrelated = swap(ExecutionStatus::Success, vec![transfer(dai, user, pool, 5)]);
+        assert_eq!(
+            classify(&unrelated),
+            Effect::NoOp("none of the claimed tokens moved".into())
+        );
+    }
+}
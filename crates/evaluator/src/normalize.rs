@@ -0,0 +1,178 @@
+use indexmap::IndexMap;
+
+use crate::model::ActionSig;
+
+/// Spellings of well-known protocols, keyed by [`protocol_key`], mapped to the slugs used
+/// in domains.enso.yaml.
+const PROTOCOL_ALIASES: &[(&str, &str)] = &[
+    ("uniswapv2", "uniswap-v2"),
+    ("univ2", "uniswap-v2"),
+    ("uniswapv3", "uniswap-v3"),
+    ("univ3", "uniswap-v3"),
+    ("uniswapv4", "uniswap-v4"),
+    ("univ4", "uniswap-v4"),
+    ("sushiswap", "sushiswap"),
+    ("sushi", "sushiswap"),
+    ("curve", "curve"),
+    ("curvefi", "curve"),
+    ("balancerv2", "balancer-v2"),
+    ("balancer", "balancer-v2"),
+    ("pancakeswapv3", "pancakeswap-v3"),
+    ("aerodrome", "aerodrome"),
+    ("velodrome", "velodrome"),
+    ("aavev2", "aave-v2"),
+    ("aavev3", "aave-v3"),
+    ("compoundv3", "compound-v3"),
+    ("comet", "compound-v3"),
+    ("morphoblue", "morpho-blue"),
+    ("spark", "spark"),
+    ("sparklend", "spark"),
+    ("lido", "lido"),
+    ("rocketpool", "rocket-pool"),
+    ("convex", "convex"),
+    ("yearnv3", "yearn-v3"),
+    ("stargate", "stargate"),
+    ("stargatev2", "stargate"),
+    ("across", "across"),
+    ("acrossv3", "across"),
+    ("layerzero", "layerzero"),
+];
+
+/// Token contracts mapped to canonical symbols: (chain id, lowercase address, symbol).
+const KNOWN_TOKENS: &[(u64, &str, &str)] = &[
+    (1, "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "USDC"),
+    (1, "0xdac17f958d2ee523a2206206994597c13d831ec7", "USDT"),
+    (1, "0x6b175474e89094c44da98b954eedeac495271d0f", "DAI"),
+    (1, "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", "WETH"),
+    (1, "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599", "WBTC"),
+    (1, "0xae7ab96520de3a18e5e111b5eaab095312d7fe84", "stETH"),
+    (1, "0x7f39c581f595b53c5cb19bd0b3f8da6c935e2ca0", "wstETH"),
+    (1, "0xae78736cd615f374d3085123a210448e74fc6393", "rETH"),
+    (10, "0x0b2c639c533813f4aa9d7837caf62653d097ff85", "USDC"),
+    (10, "0x4200000000000000000000000000000000000006", "WETH"),
+    (137, "0x3c499c542cef5e3811e1192ce70d8cc03d5c3359", "USDC"),
+    (137, "0x7ceb23fd6bc0add59e62ac25578270cff1b9f619", "WETH"),
+    (8453, "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913", "USDC"),
+    (8453, "0x4200000000000000000000000000000000000006", "WETH"),
+    (42161, "0xaf88d065e77c8cc2239327c5edb3a432268e5831", "USDC"),
+    (42161, "0x82af49447d8a07e3bd95bd0d56f35241523fbab1", "WETH"),
+];
+
+/// Case- and punctuation-insensitive key: `Uniswap_V3`, `uniswap-v3` and `UniswapV3`
+/// all become `uniswapv3`.
+pub fn protocol_key(raw: &str) -> String {
+    raw.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Canonical slug for `raw`. `extra` (canonical slug → aliases, from the domains file) is
+/// consulted before the built-in table; unknown protocols fall back to their
+/// [`protocol_key`], so casing and punctuation cannot split them either.
+pub fn canonical_protocol(raw: &str, extra: &IndexMap<String, Vec<String>>) -> String {
+    let key = protocol_key(raw);
+    if let Some((canonical, _)) = extra.iter().find(|(canonical, aliases)| {
+        protocol_key(canonical) == key || aliases.iter().any(|alias| protocol_key(alias) == key)
+    }) {
+        return canonical.clone();
+    }
+    if let Some((_, canonical)) = PROTOCOL_ALIASES.iter().find(|(alias, _)| *alias == key) {
+        return canonical.to_string();
+    }
+    key
+}
+
+/// Known token addresses become their symbol, other addresses are lowercased and
+/// symbols take the casing of a known symbol (or upper case).
+pub fn canonical_token(chain_id: u64, raw: &str) -> String {
+    let raw = raw.trim();
+    if is_address(raw) {
+        let address = raw.to_ascii_lowercase();
+        return KNOWN_TOKENS
+            .iter()
+            .find(|(chain, known, _)| *chain == chain_id && *known == address)
+            .map(|(_, _, symbol)| symbol.to_string())
+            .unwrap_or(address);
+    }
+    KNOWN_TOKENS
+        .iter()
+        .find(|(_, _, symbol)| symbol.eq_ignore_ascii_case(raw))
+        .map(|(_, _, symbol)| symbol.to_string())
+        .unwrap_or_else(|| raw.to_ascii_uppercase())
+}
+
+/// The signature with canonical protocol, tokens and recipient, so that spelling
+/// variants of one action dedupe to a single signature.
+pub fn normalize_sig(sig: &ActionSig, protocol_aliases: &IndexMap<String, Vec<String>>) -> ActionSig {
+    let token = |raw: &str| canonical_token(sig.chain_id, raw);
+    ActionSig {
+        chain_id: sig.chain_id,
+        action: sig.action.clone(),
+        protocol: sig
+            .protocol
+            .as_deref()
+            .map(|protocol| canonical_protocol(protocol, protocol_aliases)),
+        tokens: sig.tokens.as_ref().map(|(a, b)| (token(a), token(b))),
+        recipient: sig.recipient.as_deref().map(|recipient| {
+            if is_address(recipient) {
+                recipient.trim().to_ascii_lowercase()
+            } else {
+                recipient.to_string()
+            }
+        }),
+    }
+}
+
+/// Whether `symbol` is a canonical symbol of a known token on `chain_id`.
+pub fn is_known_token(chain_id: u64, symbol: &str) -> bool {
+    KNOWN_TOKENS
+        .iter()
+        .any(|(chain, _, known)| *chain == chain_id && *known == symbol)
+}
+
+fn is_address(value: &str) -> bool {
+    value.len() == 42 && value.starts_with("0x") && value[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ActionKind;
+
+    #[test]
+    fn collapses_spelling_variants() {
+        let extra = IndexMap::from([("forkswap".to_string(), vec!["ForkSwap V2".to_string()])]);
+        for raw in ["uniswap_v3", "uniswap-v3", "UniswapV3", "UNIV3"] {
+            assert_eq!(canonical_protocol(raw, &extra), "uniswap-v3");
+        }
+        assert_eq!(canonical_protocol("forkswap_v2", &extra), "forkswap");
+        assert_eq!(canonical_protocol("Some_Dex", &extra), "somedex");
+
+        let checksummed = ActionSig::new(
+            1,
+            ActionKind::Swap,
+            Some("UniswapV3".into()),
+            Some((
+                "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".into(),
+                "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".into(),
+            )),
+            None,
+        );
+        let symbols = ActionSig::new(
+            1,
+            ActionKind::Swap,
+            Some("uniswap_v3".into()),
+            Some(("usdc".into(), "weth".into())),
+            None,
+        );
+        assert_eq!(
+            normalize_sig(&checksummed, &extra),
+            normalize_sig(&symbols, &extra)
+        );
+        assert_eq!(
+            canonical_token(1, "0x1111111111111111111111111111111111111ABC"),
+            "0x1111111111111111111111111111111111111abc"
+        );
+    }
+}
@@ -1,10 +1,15 @@
 use std::{fs::File, io::BufReader, path::Path};
 
+use chrono::{DateTime, Utc};
+
 use anyhow::{Context, Result};
 use indexmap::IndexMap;
 use serde::Deserialize;
 
-use crate::model::{ActionKind, ActionSig};
+use crate::{
+    events::actions_from_logs,
+    model::{ActionKind, ActionSig},
+};
 
 pub fn load_transactions(path: impl AsRef<Path>) -> Result<Vec<ParsedTransaction>> {
     let file = File::open(path.as_ref())
@@ -17,17 +22,60 @@ pub fn read_transactions<R: std::io::Read>(reader: R) -> Result<Vec<ParsedTransa
     let mut transactions = Vec::new();
     for entry in stream {
         let artifact = entry?;
-        transactions.push(parse_artifact(artifact));
+        transactions.extend(parse_artifact(artifact));
     }
     Ok(transactions)
 }
 
-fn parse_artifact(artifact: RawArtifact) -> ParsedTransaction {
-    match artifact {
+/// The parts of the runner's meta.json the evaluator reports on.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunMeta {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    #[serde(default)]
+    pub llm_usage: Option<RunLlmUsage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunLlmUsage {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub calls: u32,
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub total_tokens: u64,
+    #[serde(default)]
+    pub latency_ms: u64,
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+}
+
+/// Loads the meta.json written next to a per_tx.jsonl, if there is one.
+pub fn load_run_meta(per_tx: impl AsRef<Path>) -> Result<Option<RunMeta>> {
+    let Some(path) = per_tx.as_ref().parent().map(|dir| dir.join("meta.json")) else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+    let meta = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("invalid run metadata at {}", path.display()))?;
+    Ok(Some(meta))
+}
+
+fn parse_artifact(artifact: RawArtifact) -> Option<ParsedTransaction> {
+    let parsed = match artifact {
         RawArtifact::Route {
+            intent_id,
             request,
             response,
             execution,
+            policy_violations,
         } => {
             let chain_id = response.tx.chain_id;
             let actions = extract_actions_from_route(chain_id, response.route.as_ref());
@@ -38,14 +86,23 @@ fn parse_artifact(artifact: RawArtifact) -> ParsedTransaction {
                     .as_ref()
                     .map(|e| e.status)
                     .unwrap_or(ExecutionStatus::Skipped),
+                transaction_hash: execution.as_ref().and_then(|e| e.transaction_hash.clone()),
+                executed_actions: execution
+                    .as_ref()
+                    .map(|record| actions_from_logs(chain_id, &record.logs))
+                    .unwrap_or_default(),
                 execution_logs: execution.map(|record| record.logs).unwrap_or_default(),
                 request_recipient: extract_recipient(&request),
+                policy_violations,
+                intent_id,
             }
         }
         RawArtifact::Bundle {
+            intent_id,
             request,
             response,
             execution,
+            policy_violations,
         } => {
             let chain_id = response.tx.chain_id;
             let mut actions = Vec::new();
@@ -58,14 +115,25 @@ fn parse_artifact(artifact: RawArtifact) -> ParsedTransaction {
                     .as_ref()
                     .map(|e| e.status)
                     .unwrap_or(ExecutionStatus::Skipped),
+                transaction_hash: execution.as_ref().and_then(|e| e.transaction_hash.clone()),
+                executed_actions: execution
+                    .as_ref()
+                    .map(|record| actions_from_logs(chain_id, &record.logs))
+                    .unwrap_or_default(),
                 execution_logs: execution.map(|record| record.logs).unwrap_or_default(),
                 request_recipient: extract_recipient(&request),
+                policy_violations,
+                intent_id,
             }
         }
-    }
+        // Malformed LLM plans never produced a transaction.
+        RawArtifact::PlanFailure {} => return None,
+    };
+    Some(parsed)
 }
 
-fn extract_actions_from_route(chain_id: u64, route: Option<&RouteMetadata>) -> Vec<ActionSig> {
+/// Steps after a bridge run on its destination chain, so they carry that chain id.
+fn extract_actions_from_route(mut chain_id: u64, route: Option<&RouteMetadata>) -> Vec<ActionSig> {
     let mut out = Vec::new();
     if let Some(route) = route {
         for step in &route.steps {
@@ -89,8 +157,9 @@ fn extract_actions_from_route(chain_id: u64, route: Option<&RouteMetadata>) -> V
                     destination_chain,
                     ..
                 } => {
+                    chain_id = destination_chain.unwrap_or(chain_id);
                     out.push(ActionSig::new(
-                        destination_chain.unwrap_or(chain_id),
+                        chain_id,
                         ActionKind::Bridge,
                         protocol.clone(),
                         None,
@@ -116,7 +185,8 @@ fn extract_actions_from_route(chain_id: u64, route: Option<&RouteMetadata>) -> V
     out
 }
 
-fn extract_actions_from_bundle(chain_id: u64, bundle: Option<&BundleMetadata>) -> Vec<ActionSig> {
+/// Like [`extract_actions_from_route`], actions after a bridge carry its destination chain.
+fn extract_actions_from_bundle(mut chain_id: u64, bundle: Option<&BundleMetadata>) -> Vec<ActionSig> {
     let mut out = Vec::new();
     if let Some(bundle) = bundle {
         for action in &bundle.actions {
@@ -188,8 +258,9 @@ fn extract_actions_from_bundle(chain_id: u64, bundle: Option<&BundleMetadata>) -
                     destination_chain,
                     recipient,
                 } => {
+                    chain_id = destination_chain.unwrap_or(chain_id);
                     out.push(ActionSig::new(
-                        destination_chain.unwrap_or(chain_id),
+                        chain_id,
                         ActionKind::Bridge,
                         protocol.clone(),
                         None,
@@ -226,17 +297,27 @@ fn token_pair(a: Option<String>, b: Option<String>) -> Option<(String, String)>
 #[serde(tag = "type", rename_all = "lowercase")]
 enum RawArtifact {
     Route {
+        #[serde(default)]
+        intent_id: Option<String>,
         request: serde_json::Value,
         response: RouteResponse,
         #[serde(default)]
         execution: Option<ExecutionRecord>,
+        #[serde(default)]
+        policy_violations: Vec<PolicyViolation>,
     },
     Bundle {
+        #[serde(default)]
+        intent_id: Option<String>,
         request: serde_json::Value,
         response: BundleResponse,
         #[serde(default)]
         execution: Option<ExecutionRecord>,
+        #[serde(default)]
+        policy_violations: Vec<PolicyViolation>,
     },
+    #[serde(rename = "plan_failure")]
+    PlanFailure {},
 }
 
 #[derive(Debug, Deserialize)]
@@ -255,7 +336,7 @@ struct BundleResponse {
     route: Option<RouteMetadata>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct TransactionEnvelope {
     pub to: String,
     pub data: String,
@@ -351,16 +432,32 @@ pub enum ExecutionStatus {
 struct ExecutionRecord {
     status: ExecutionStatus,
     #[serde(default)]
+    transaction_hash: Option<String>,
+    #[serde(default)]
     logs: Vec<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ParsedTransaction {
     pub envelope: TransactionEnvelope,
     pub actions: Vec<ActionSig>,
     pub execution_status: ExecutionStatus,
+    pub transaction_hash: Option<String>,
+    /// Actions decoded from the simulation's event logs, see [`actions_from_logs`].
+    pub executed_actions: Vec<ActionSig>,
     pub execution_logs: Vec<String>,
     pub request_recipient: Option<String>,
+    pub policy_violations: Vec<PolicyViolation>,
+    /// Scenario intent the line answers, when the producer recorded one.
+    pub intent_id: Option<String>,
+}
+
+/// A plan rule the runner saw broken (`too_many_actions`, `unknown_address`, ...).
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicyViolation {
+    pub rule: String,
+    #[serde(default)]
+    pub message: String,
 }
 
 fn extract_recipient(request: &serde_json::Value) -> Option<String> {
@@ -369,3 +466,26 @@ fn extract_recipient(request: &serde_json::Value) -> Option<String> {
         .and_then(|value| value.as_str())
         .map(|s| s.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_plan_failures() {
+        let input = concat!(
+            r#"{"type":"plan_failure","timestamp":"2024-01-01T00:00:00Z","raw":{"choices":[]},"error":"bad"}"#,
+            "\n",
+            r#"{"type":"route","request":{},"response":{"tx":{"to":"0x0","data":"0x","value":"0x0","chainId":1}}}"#,
+        );
+        let transactions = read_transactions(input.as_bytes()).expect("parse artifacts");
+        assert_eq!(transactions.len(), 1);
+    }
+
+    #[test]
+    fn reads_policy_violations() {
+        let input = r#"{"type":"bundle","request":{},"response":{"tx":{"to":"0x0","data":"0x","value":"0x0","chainId":1}},"policy_violations":[{"rule":"too_many_actions","message":"bundle has 6 actions"}]}"#;
+        let transactions = read_transactions(input.as_bytes()).expect("parse artifacts");
+        assert_eq!(transactions[0].policy_violations[0].rule, "too_many_actions");
+    }
+}
@@ -1,127 +1,771 @@
 use indexmap::{IndexMap, IndexSet};
 
 use crate::{
-    config::DomainsConfig,
-    model::{ActionKind, ActionSig, ScoreMeta, ScoreReport},
-    parse::{ExecutionStatus, ParsedTransaction},
+    calldata::check_calldata,
+    config::{DomainsConfig, PenaltyConfig, UNCLASSIFIED},
+    events::{cross_check, EventCheck},
+    model::{
+        ActionExplanation, ActionKind, ActionSig, BonusItem, BonusKind, CoverageGaps, Efficiency,
+        PenaltyItem, PenaltyKind, ScoreMeta, ScoreReport, TxExplanation,
+    },
+    noop::{classify, Effect},
+    normalize::normalize_sig,
+    parse::{ParsedTransaction, RunMeta},
 };
 
-const COMPOSITION_BONUS_PER_EXTRA_ACTION: f64 = 0.25;
+/// Subtracted once per SPEC §8 plan rule the runner recorded as broken.
+const POLICY_VIOLATION_PENALTY: f64 = 0.5;
+
+/// Catch-all domains; they never count towards the domain diversity bonus.
+const CATCH_ALL_DOMAINS: [&str; 2] = [UNCLASSIFIED, "other"];
 
 pub fn score(transactions: &[ParsedTransaction], domains: &DomainsConfig) -> ScoreReport {
+    evaluate(transactions, domains).0
+}
+
+/// Per-transaction breakdown of [`score`]: which signatures counted and why.
+pub fn explain(transactions: &[ParsedTransaction], domains: &DomainsConfig) -> Vec<TxExplanation> {
+    evaluate(transactions, domains).1
+}
+
+fn evaluate(
+    transactions: &[ParsedTransaction],
+    domains: &DomainsConfig,
+) -> (ScoreReport, Vec<TxExplanation>) {
     let mut domain_sets: IndexMap<String, IndexSet<ActionSig>> = IndexMap::new();
+    // Sum of the weights of each domain's unique signatures.
+    let mut by_domain: IndexMap<String, f64> = IndexMap::new();
+    // Transaction that first earned each signature, for `already counted` reasons.
+    let mut first_seen: IndexMap<ActionSig, usize> = IndexMap::new();
+    let mut explanations = Vec::with_capacity(transactions.len());
     let mut bonus = 0.0;
+    let mut penalties = Vec::new();
+    let mut repeats = RepeatTracker::default();
     let mut counted_transactions = 0usize;
 
-    for tx in transactions {
-        if matches!(tx.execution_status, ExecutionStatus::Revert) {
+    for (index, tx) in transactions.iter().enumerate() {
+        // Rule violations cost even when the transaction reverted.
+        let tx_penalty = POLICY_VIOLATION_PENALTY * tx.policy_violations.len() as f64;
+        penalties.extend(tx.policy_violations.iter().map(|violation| PenaltyItem {
+            kind: PenaltyKind::PolicyViolation,
+            tx_index: index,
+            sig: None,
+            amount: POLICY_VIOLATION_PENALTY,
+            reason: format!("policy violation: {}", violation.rule),
+        }));
+        // Canonical spellings, so casing and aliases cannot split one signature.
+        let actions: Vec<ActionSig> = tx
+            .actions
+            .iter()
+            .map(|action| normalize_sig(action, &domains.protocol_aliases))
+            .collect();
+        let tx_domains: IndexSet<String> = actions.iter().map(|action| domains.classify(action).0).collect();
+        let mut explanation = TxExplanation {
+            intent_id: tx.intent_id.clone().unwrap_or_else(|| format!("tx-{index}")),
+            index,
+            chain_id: tx.envelope.chain_id,
+            tx_hash: tx.transaction_hash.clone(),
+            domains: tx_domains.into_iter().collect(),
+            ignored: None,
+            actions: Vec::with_capacity(actions.len()),
+            bonus: 0.0,
+            bonuses: Vec::new(),
+            penalty: tx_penalty,
+            score: -tx_penalty,
+            event_check: EventCheck::default(),
+            calldata: None,
+            notes: tx
+                .policy_violations
+                .iter()
+                .map(|violation| match violation.message.as_str() {
+                    "" => format!("policy violation: {}", violation.rule),
+                    message => format!("policy violation: {} ({message})", violation.rule),
+                })
+                .collect(),
+        };
+        let envelope = &tx.envelope;
+        let check = check_calldata(
+            &envelope.to,
+            &envelope.data,
+            &tx.actions,
+            &domains.enso_deployments,
+        );
+        if check.non_enso_to {
+            explanation
+                .notes
+                .push(format!("tx.to {} is not an Enso router or delegate", envelope.to));
+        }
+        if !check.unmatched_actions.is_empty() {
+            let count = check.unmatched_actions.len();
+            explanation
+                .notes
+                .push(format!("{count} claimed action(s) without a matching command"));
+        }
+        explanation.calldata = Some(check);
+        if !tx.execution_logs.is_empty() {
+            explanation.event_check = cross_check(tx.envelope.chain_id, &tx.actions, &tx.executed_actions);
+            let check = &explanation.event_check;
+            if !check.unconfirmed.is_empty() {
+                let count = check.unconfirmed.len();
+                explanation
+                    .notes
+                    .push(format!("{count} claimed action(s) not confirmed by events"));
+            }
+            if !check.unclaimed.is_empty() {
+                let count = check.unclaimed.len();
+                explanation
+                    .notes
+                    .push(format!("{count} executed action(s) missing from metadata"));
+            }
+        }
+
+        let effect = classify(tx);
+        if effect == Effect::Unsimulated && domains.count_unsimulated {
+            explanation.notes.push("not simulated".into());
+        } else if effect != Effect::Effective {
+            if let Effect::NoOp(why) = &effect {
+                explanation.notes.push(format!("no effect: {why}"));
+            }
+            explanation.ignored = Some(effect.label().into());
+            explanation.actions = actions
+                .iter()
+                .map(|action| ActionExplanation {
+                    sig: action.clone(),
+                    domain: domains.classify(action).0,
+                    counted: false,
+                    points: 0.0,
+                    reason: effect.reason(),
+                })
+                .collect();
+            explanations.push(explanation);
             continue;
         }
 
         counted_transactions += 1;
 
-        let mut kinds_in_tx: IndexSet<ActionKind> = IndexSet::new();
-        for action in &tx.actions {
-            kinds_in_tx.insert(action.action.clone());
-            let domain = domains
-                .domain_for_kind(&action.action)
-                .unwrap_or_else(|| "unclassified".to_string());
-            domain_sets.entry(domain).or_default().insert(action.clone());
+        for action in &actions {
+            let (domain, weight) = domains.classify(action);
+            let (counted, points, reason) = if let Some(first) = first_seen.get(action) {
+                (false, 0.0, format!("already counted in tx #{first}"))
+            } else {
+                first_seen.insert(action.clone(), index);
+                *by_domain.entry(domain.clone()).or_insert(0.0) += weight;
+                let mut reason = format!("new {domain} signature (weight {weight})");
+                for (kind, amount, why) in repeats.charge(&domains.penalties, action, weight) {
+                    reason.push_str(&format!("; {why} (-{amount})"));
+                    explanation.penalty += amount;
+                    explanation.score -= amount;
+                    penalties.push(PenaltyItem {
+                        kind,
+                        tx_index: index,
+                        sig: Some(action.clone()),
+                        amount,
+                        reason: why,
+                    });
+                }
+                (true, weight, reason)
+            };
+            domain_sets
+                .entry(domain.clone())
+                .or_default()
+                .insert(action.clone());
+            explanation.score += points;
+            explanation.actions.push(ActionExplanation {
+                sig: action.clone(),
+                domain,
+                counted,
+                points,
+                reason,
+            });
         }
 
-        if !kinds_in_tx.is_empty() {
-            let unique = kinds_in_tx.len().min(domains.per_tx_action_limit);
-            if unique > 1 {
-                bonus += COMPOSITION_BONUS_PER_EXTRA_ACTION * (unique as f64 - 1.0);
-            }
-        }
+        explanation.bonuses = tx_bonuses(domains, tx.envelope.chain_id, &actions, &explanation.domains);
+        explanation.bonus = explanation.bonuses.iter().map(|item| item.amount).sum();
+        explanation.score += explanation.bonus;
+        bonus += explanation.bonus;
+        explanations.push(explanation);
     }
 
-    let mut by_domain = IndexMap::new();
-    let mut unique_sigs = IndexMap::new();
-    let mut base = 0.0;
-
-    for (domain, set) in domain_sets {
-        let weight = domains.domains.get(&domain).map(|cfg| cfg.weight).unwrap_or(1.0);
-        let score = weight * set.len() as f64;
-        base += score;
-        by_domain.insert(domain.clone(), score);
-        unique_sigs.insert(domain, set.into_iter().collect());
-    }
+    let unique_sigs: IndexMap<String, Vec<ActionSig>> = domain_sets
+        .into_iter()
+        .map(|(domain, set)| (domain, set.into_iter().collect()))
+        .collect();
+    let base = by_domain.values().sum::<f64>();
 
-    let final_score = base + bonus; // penalty hooks in future
+    let penalty = penalties.iter().map(|item| item.amount).sum::<f64>();
+    let final_score = base + bonus - penalty;
 
     let unique_total: usize = unique_sigs
         .values()
         .map(|v: &Vec<ActionSig>| v.len())
         .sum::<usize>();
 
-    ScoreReport {
+    let report = ScoreReport {
         final_score,
         by_domain,
         bonus,
-        penalty: 0.0,
+        penalty,
+        penalties,
         unique_sigs,
         metadata: ScoreMeta {
             total_transactions: counted_transactions,
             unique_action_signatures: unique_total,
         },
+        efficiency: None,
+    };
+    (report, explanations)
+}
+
+/// The [`BonusConfig`] bonuses one successful transaction earns. Every "per extra"
+/// count is capped at `per_tx_action_limit`.
+fn tx_bonuses(
+    domains: &DomainsConfig,
+    chain_id: u64,
+    actions: &[ActionSig],
+    tx_domains: &[String],
+) -> Vec<BonusItem> {
+    let config = &domains.bonuses;
+    let extra = |distinct: usize| distinct.min(domains.per_tx_action_limit).saturating_sub(1);
+    let mut items = Vec::new();
+    let mut push = |kind, rate: f64, count: usize, reason: String| {
+        if rate > 0.0 && count > 0 {
+            items.push(BonusItem {
+                kind,
+                amount: rate * count as f64,
+                reason,
+            });
+        }
+    };
+
+    let kinds: IndexSet<&ActionKind> = actions.iter().map(|action| &action.action).collect();
+    push(
+        BonusKind::Composition,
+        config.composition,
+        extra(kinds.len()),
+        format!("{} distinct action kinds", kinds.len()),
+    );
+
+    // A bridge off the source chain, then an action on the bridge's destination chain.
+    let cross_chain = actions
+        .iter()
+        .enumerate()
+        .filter(|(_, action)| action.action == ActionKind::Bridge && action.chain_id != chain_id)
+        .find_map(|(at, bridge)| {
+            actions[at + 1..]
+                .iter()
+                .find(|action| {
+                    action.chain_id == bridge.chain_id
+                        && !matches!(action.action, ActionKind::Bridge | ActionKind::Approve)
+                })
+                .map(|action| (bridge, action))
+        });
+    if let Some((bridge, action)) = cross_chain {
+        let reason = format!(
+            "bridge to chain {} followed by {}",
+            bridge.chain_id, action.action
+        );
+        push(BonusKind::CrossChain, config.cross_chain, 1, reason);
+    }
+
+    let protocols: IndexSet<String> = actions
+        .iter()
+        .filter_map(|action| action.protocol.as_deref().map(str::to_lowercase))
+        .collect();
+    push(
+        BonusKind::MultiProtocol,
+        config.multi_protocol,
+        extra(protocols.len()),
+        format!("{} distinct protocols", protocols.len()),
+    );
+    let diverse: Vec<&String> = tx_domains
+        .iter()
+        .filter(|domain| !CATCH_ALL_DOMAINS.contains(&domain.as_str()))
+        .collect();
+    push(
+        BonusKind::DomainDiversity,
+        config.domain_diversity,
+        extra(diverse.len()),
+        format!("{} distinct domains", diverse.len()),
+    );
+    items
+}
+
+/// Signatures already credited, for the repetition penalties in [`PenaltyConfig`].
+#[derive(Default)]
+struct RepeatTracker {
+    per_action: IndexMap<(ActionKind, Option<String>), usize>,
+    per_protocol: IndexMap<String, usize>,
+    /// Everything but the token pair, per credited signature.
+    shapes: IndexSet<(u64, ActionKind, Option<String>, Option<String>)>,
+}
+
+impl RepeatTracker {
+    /// Records a newly credited signature and returns the deductions it incurs, never
+    /// more than `weight` in total.
+    fn charge(
+        &mut self,
+        config: &PenaltyConfig,
+        sig: &ActionSig,
+        weight: f64,
+    ) -> Vec<(PenaltyKind, f64, String)> {
+        let protocol = sig.protocol.as_deref().map(str::to_lowercase);
+        let label = format!("{} via {}", sig.action, protocol.as_deref().unwrap_or("?"));
+        let seen = self
+            .per_action
+            .entry((sig.action.clone(), protocol.clone()))
+            .or_default();
+        *seen += 1;
+        let nth = *seen;
+        let protocol_nth = protocol.clone().map(|protocol| {
+            let count = self.per_protocol.entry(protocol).or_default();
+            *count += 1;
+            *count
+        });
+        let near_duplicate = !self.shapes.insert((
+            sig.chain_id,
+            sig.action.clone(),
+            protocol.clone(),
+            sig.recipient.clone(),
+        ));
+
+        if let (Some(cap), Some(count)) = (config.protocol_cap, protocol_nth) {
+            if count > cap {
+                let reason = format!(
+                    "signature #{count} for protocol {} exceeds the cap of {cap}",
+                    protocol.unwrap_or_default()
+                );
+                return vec![(PenaltyKind::ProtocolCap, weight, reason)];
+            }
+        }
+
+        let mut charges = Vec::new();
+        let mut remaining = weight;
+        if let Some(cooldown) = &config.cooldown {
+            if nth > cooldown.full_credit {
+                let k = (nth - cooldown.full_credit) as i32;
+                let amount = weight * (1.0 - cooldown.decay.powi(k));
+                if amount > 0.0 {
+                    remaining -= amount;
+                    let reason = format!("{label} #{nth} after {} at full credit", cooldown.full_credit);
+                    charges.push((PenaltyKind::Cooldown, amount, reason));
+                }
+            }
+        }
+        if near_duplicate && config.near_duplicate > 0.0 && remaining > 0.0 {
+            let amount = config.near_duplicate.min(remaining);
+            let reason = format!("{label} repeated with only a different token pair");
+            charges.push((PenaltyKind::NearDuplicate, amount, reason));
+        }
+        charges
+    }
+}
+
+/// Lists the domains, actions, protocols and chains in `domains` that the report
+/// has no unique signature for yet. `unknown` actions are never suggested.
+pub fn coverage_gaps(report: &ScoreReport, domains: &DomainsConfig) -> CoverageGaps {
+    let mut gaps = CoverageGaps::default();
+    let mut seen_chains: IndexSet<u64> = IndexSet::new();
+
+    for (name, domain) in &domains.domains {
+        let sigs = report
+            .unique_sigs
+            .get(name)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        seen_chains.extend(sigs.iter().map(|sig| sig.chain_id));
+        if sigs.is_empty() {
+            gaps.uncovered_domains.push(name.clone());
+        }
+
+        let actions: Vec<ActionKind> = domain
+            .allow
+            .iter()
+            .map(|entry| entry.action.clone())
+            .filter(|kind| *kind != ActionKind::Unknown && !sigs.iter().any(|sig| &sig.action == kind))
+            .collect();
+        if !actions.is_empty() {
+            gaps.uncovered_actions.insert(name.clone(), actions);
+        }
+
+        let protocols: Vec<String> = domain
+            .protocols
+            .iter()
+            .filter(|protocol| {
+                !sigs.iter().any(|sig| {
+                    sig.protocol
+                        .as_deref()
+                        .is_some_and(|seen| domains.same_protocol(seen, protocol))
+                })
+            })
+            .cloned()
+            .collect();
+        if !protocols.is_empty() {
+            gaps.uncovered_protocols.insert(name.clone(), protocols);
+        }
+    }
+
+    for sigs in report.unique_sigs.values() {
+        seen_chains.extend(sigs.iter().map(|sig| sig.chain_id));
+    }
+    gaps.uncovered_chains = domains
+        .chains
+        .iter()
+        .copied()
+        .filter(|chain| !seen_chains.contains(chain))
+        .collect();
+    gaps
+}
+
+/// Relates a score to the time and LLM spend recorded in the run's meta.json.
+pub fn efficiency(final_score: f64, meta: &RunMeta) -> Efficiency {
+    let wall_clock_seconds = (meta.finished_at - meta.started_at).num_milliseconds().max(0) as f64 / 1000.0;
+    let usage = meta.llm_usage.as_ref();
+    let cost_usd = usage.and_then(|usage| usage.cost_usd);
+
+    Efficiency {
+        wall_clock_seconds,
+        score_per_second: (wall_clock_seconds > 0.0).then(|| final_score / wall_clock_seconds),
+        model: usage.and_then(|usage| usage.model.clone()),
+        llm_calls: usage.map(|usage| usage.calls).unwrap_or(0),
+        prompt_tokens: usage.map(|usage| usage.prompt_tokens).unwrap_or(0),
+        completion_tokens: usage.map(|usage| usage.completion_tokens).unwrap_or(0),
+        total_tokens: usage.map(|usage| usage.total_tokens).unwrap_or(0),
+        llm_latency_seconds: usage.map(|usage| usage.latency_ms as f64 / 1000.0).unwrap_or(0.0),
+        cost_usd,
+        score_per_dollar: cost_usd.filter(|cost| *cost > 0.0).map(|cost| final_score / cost),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{DomainConfig, DomainsConfig};
+    use crate::config::{BonusConfig, CooldownConfig, DomainsConfig};
     use crate::model::{ActionKind, ActionSig};
     use crate::parse::{ExecutionStatus, ParsedTransaction};
-    use indexmap::IndexMap;
 
-    #[test]
-    fn applies_domain_weights_and_bonus() {
-        let domains = DomainsConfig {
-            version: "0.1.0".into(),
-            per_tx_action_limit: 6,
-            domains: IndexMap::from([
-                (
-                    "dex".into(),
-                    DomainConfig {
-                        weight: 1.0,
-                        allow: vec![super::super::config::AllowEntry {
-                            action: ActionKind::Swap,
-                        }],
-                    },
-                ),
-                (
-                    "lending".into(),
-                    DomainConfig {
-                        weight: 1.25,
-                        allow: vec![super::super::config::AllowEntry {
-                            action: ActionKind::Deposit,
-                        }],
-                    },
-                ),
-            ]),
-        };
+    fn test_domains() -> DomainsConfig {
+        serde_yaml::from_str(
+            r#"
+version: "0.1.0"
+per_tx_action_limit: 6
+chains: [1, 8453]
+domains:
+  dex:
+    weight: 1.0
+    allow:
+      - action: swap
+    protocols: [uniswap-v3, curve]
+  lending:
+    weight: 1.25
+    allow:
+      - action: deposit
+"#,
+        )
+        .unwrap()
+    }
 
-        let tx = ParsedTransaction {
+    /// A simulated transaction whose logs show 1 USDC leaving the sender.
+    fn transaction(actions: Vec<ActionSig>) -> ParsedTransaction {
+        let usdc_transfer = serde_json::json!({
+            "address": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+            "topics": [
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                format!("0x{:0>64}", "1111"),
+                format!("0x{:0>64}", "2222"),
+            ],
+            "data": format!("0x{:064x}", 1_000_000),
+        });
+        ParsedTransaction {
             envelope: crate::parse::TransactionEnvelope {
-                to: "0x0".into(),
+                to: "0xF75584eF6673aD213a685a1B58Cc0330B8eA22Cf".into(),
                 data: "0x".into(),
                 value: "0".into(),
                 chain_id: 1,
             },
-            actions: vec![
-                ActionSig::new(1, ActionKind::Swap, Some("uniswap".into()), None, None),
-                ActionSig::new(1, ActionKind::Deposit, Some("aave".into()), None, None),
-            ],
+            actions,
             execution_status: ExecutionStatus::Success,
-            execution_logs: Vec::new(),
+            execution_logs: vec![usdc_transfer.to_string()],
+            transaction_hash: None,
+            executed_actions: Vec::new(),
+            intent_id: None,
             request_recipient: None,
-        };
+            policy_violations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn excludes_no_ops_and_unsimulated() {
+        let mut domains = test_domains();
+        let swap = ActionSig::new(1, ActionKind::Swap, Some("curve".into()), None, None);
+        let mut no_op = transaction(vec![swap.clone()]);
+        no_op.execution_logs.clear();
+        let mut unsimulated = transaction(vec![swap]);
+        unsimulated.execution_status = ExecutionStatus::Skipped;
+        unsimulated.execution_logs.clear();
+
+        let (report, explanations) = evaluate(&[no_op.clone(), unsimulated.clone()], &domains);
+        assert_eq!(report.metadata.total_transactions, 0);
+        assert_eq!(explanations[0].ignored.as_deref(), Some("no_op"));
+        assert_eq!(explanations[1].ignored.as_deref(), Some("unsimulated"));
+
+        domains.count_unsimulated = true;
+        let (report, explanations) = evaluate(&[no_op, unsimulated], &domains);
+        assert_eq!(report.metadata.total_transactions, 1);
+        assert_eq!(explanations[1].ignored, None);
+        assert!(explanations[1].notes.contains(&"not simulated".to_string()));
+    }
+
+    #[test]
+    fn applies_domain_weights_and_bonus() {
+        let domains = test_domains();
+        let tx = transaction(vec![
+            ActionSig::new(1, ActionKind::Swap, Some("uniswap".into()), None, None),
+            ActionSig::new(1, ActionKind::Deposit, Some("aave".into()), None, None),
+        ]);
 
-        let report = score(&[tx], &domains);
+        let report = score(std::slice::from_ref(&tx), &domains);
         assert!(report.final_score > 1.0);
         assert!(report.bonus > 0.0);
+
+        let explained = explain(&[tx.clone(), tx.clone()], &domains);
+        assert_eq!(explained[0].score, report.final_score);
+        assert_eq!(
+            (explained[1].intent_id.as_str(), &explained[1].domains[..]),
+            ("tx-1", &["dex".to_string(), "lending".to_string()][..])
+        );
+        assert!(explained[1]
+            .actions
+            .iter()
+            .all(|a| !a.counted && a.reason == "already counted in tx #0"));
+        assert_eq!(explained[1].score, report.bonus);
+
+        let mut violating = tx;
+        violating.policy_violations.push(crate::parse::PolicyViolation {
+            rule: "too_many_actions".into(),
+            message: String::new(),
+        });
+        let penalized = score(std::slice::from_ref(&violating), &domains);
+        assert_eq!(penalized.penalty, 0.5);
+        assert_eq!(
+            explain(&[violating], &domains)[0].notes[0],
+            "policy violation: too_many_actions"
+        );
+        assert_eq!(penalized.final_score, report.final_score - 0.5);
+
+        let gaps = coverage_gaps(&report, &domains);
+        assert!(gaps.uncovered_domains.is_empty());
+        assert_eq!(
+            gaps.uncovered_protocols["dex"],
+            vec!["uniswap-v3".to_string(), "curve".to_string()]
+        );
+        assert_eq!(gaps.uncovered_chains, vec![8453]);
+    }
+
+    #[test]
+    fn spelling_variants_count_once() {
+        let swap = |protocol: &str, token_in: &str| {
+            ActionSig::new(
+                1,
+                ActionKind::Swap,
+                Some(protocol.into()),
+                Some((token_in.into(), "WETH".into())),
+                None,
+            )
+        };
+        let transactions = [
+            transaction(vec![swap(
+                "uniswap_v3",
+                "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+            )]),
+            transaction(vec![swap(
+                "UniswapV3",
+                "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+            )]),
+            transaction(vec![swap("uniswap-v3", "usdc")]),
+        ];
+        let report = score(&transactions, &test_domains());
+        assert_eq!(report.metadata.unique_action_signatures, 1);
+        assert_eq!(
+            report.unique_sigs["dex"][0].protocol.as_deref(),
+            Some("uniswap-v3")
+        );
+    }
+
+    #[test]
+    fn unknown_protocol_spellings_count_once() {
+        let deposit = |protocol: &str| {
+            transaction(vec![ActionSig::new(
+                1,
+                ActionKind::Deposit,
+                Some(protocol.into()),
+                None,
+                None,
+            )])
+        };
+        let transactions = [deposit("SomeDex"), deposit("some_dex"), deposit("Some.Dex")];
+        let report = score(&transactions, &test_domains());
+        assert_eq!(report.metadata.unique_action_signatures, 1);
+        assert_eq!(report.by_domain["lending"], 1.25);
+    }
+
+    #[test]
+    fn itemizes_configured_bonuses() {
+        let mut domains = test_domains();
+        domains.bonuses = BonusConfig {
+            composition: 0.25,
+            cross_chain: 0.25,
+            multi_protocol: 0.1,
+            domain_diversity: 0.25,
+        };
+        let tx = transaction(vec![
+            ActionSig::new(1, ActionKind::Swap, Some("uniswap".into()), None, None),
+            ActionSig::new(10, ActionKind::Bridge, Some("stargate".into()), None, None),
+            ActionSig::new(10, ActionKind::Deposit, Some("aave".into()), None, None),
+        ]);
+
+        let explained = explain(std::slice::from_ref(&tx), &domains);
+        let bonuses: Vec<_> = explained[0]
+            .bonuses
+            .iter()
+            .map(|item| (item.kind, item.amount))
+            .collect();
+        assert_eq!(
+            bonuses,
+            [
+                (BonusKind::Composition, 0.5),
+                (BonusKind::CrossChain, 0.25),
+                (BonusKind::MultiProtocol, 0.2),
+                (BonusKind::DomainDiversity, 0.25),
+            ]
+        );
+        assert_eq!(
+            score(std::slice::from_ref(&tx), &domains).bonus,
+            explained[0].bonus
+        );
+
+        // Still on the source chain after the bridge: no destination action.
+        let mut same_chain = tx;
+        same_chain.actions[2].chain_id = 1;
+        let explained = explain(&[same_chain], &domains);
+        assert!(explained[0]
+            .bonuses
+            .iter()
+            .all(|item| item.kind != BonusKind::CrossChain));
+    }
+
+    #[test]
+    fn catch_all_domains_earn_no_diversity() {
+        let mut domains = test_domains();
+        domains.bonuses.domain_diversity = 0.25;
+        let other = serde_yaml::from_str("{weight: 0.5, allow: [{action: approve}]}").unwrap();
+        domains.domains.insert("other".into(), other);
+        let tx = transaction(vec![
+            ActionSig::new(1, ActionKind::Approve, None, None, None),
+            ActionSig::new(1, ActionKind::Swap, Some("somedex".into()), None, None),
+        ]);
+
+        let explained = explain(&[tx], &domains);
+        assert_eq!(explained[0].domains, ["other", "dex"]);
+        assert!(explained[0]
+            .bonuses
+            .iter()
+            .all(|item| item.kind != BonusKind::DomainDiversity));
+    }
+
+    #[test]
+    fn pays_cross_chain_for_a_parsed_bundle() {
+        let mut domains = test_domains();
+        domains.bonuses.cross_chain = 0.25;
+        let log = serde_json::json!({
+            "address": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+            "topics": [
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                format!("0x{:0>64}", "1111"),
+                format!("0x{:0>64}", "2222"),
+            ],
+            "data": format!("0x{:064x}", 1_000_000),
+        });
+        let line = serde_json::json!({
+            "type": "bundle",
+            "request": {},
+            "response": {
+                "tx": {"to": "0x0", "data": "0x", "value": "0x0", "chainId": 1},
+                "bundle": {"actions": [
+                    {"action": "bridge", "protocol": "stargate", "destination_chain": 10},
+                    {"action": "deposit", "protocol": "aave"},
+                ]},
+            },
+            "execution": {"status": "success", "logs": [log.to_string()]},
+        });
+        let transactions = crate::parse::read_transactions(line.to_string().as_bytes()).unwrap();
+        assert_eq!(transactions[0].actions[1].chain_id, 10);
+
+        let explained = explain(&transactions, &domains);
+        assert!(explained[0]
+            .bonuses
+            .iter()
+            .any(|item| item.kind == BonusKind::CrossChain && item.amount == 0.25));
+    }
+
+    #[test]
+    fn itemizes_repetition_penalties() {
+        let mut domains = test_domains();
+        domains.penalties = PenaltyConfig {
+            cooldown: Some(CooldownConfig {
+                full_credit: 2,
+                decay: 0.5,
+            }),
+            protocol_cap: Some(3),
+            near_duplicate: 0.25,
+        };
+        let swap = |token: &str| {
+            ActionSig::new(
+                1,
+                ActionKind::Swap,
+                Some("uniswap".into()),
+                Some(("USDC".into(), token.into())),
+                None,
+            )
+        };
+        let transactions: Vec<_> = ["WETH", "DAI", "WBTC", "LINK"]
+            .into_iter()
+            .map(|token| transaction(vec![swap(token)]))
+            .collect();
+
+        let report = score(&transactions, &domains);
+        let charged: Vec<_> = report
+            .penalties
+            .iter()
+            .map(|item| (item.tx_index, item.kind, item.amount))
+            .collect();
+        assert_eq!(
+            charged,
+            [
+                (1, PenaltyKind::NearDuplicate, 0.25),
+                (2, PenaltyKind::Cooldown, 0.5),
+                (2, PenaltyKind::NearDuplicate, 0.25),
+                (3, PenaltyKind::ProtocolCap, 1.0),
+            ]
+        );
+        assert_eq!(report.final_score, 4.0 - 2.0);
+        let explained = explain(&transactions, &domains);
+        assert_eq!(
+            explained.iter().map(|tx| tx.score).sum::<f64>(),
+            report.final_score
+        );
+    }
+
+    #[test]
+    fn efficiency_divides_score_by_time_and_cost() {
+        let meta: RunMeta = serde_json::from_value(serde_json::json!({
+            "started_at": "2024-01-01T00:00:00Z",
+            "finished_at": "2024-01-01T00:00:10Z",
+            "llm_usage": { "calls": 2, "total_tokens": 1200, "latency_ms": 4000, "cost_usd": 0.5 }
+        }))
+        .unwrap();
+        let efficiency = efficiency(5.0, &meta);
+        assert_eq!(efficiency.score_per_second, Some(0.5));
+        assert_eq!(efficiency.score_per_dollar, Some(10.0));
+        assert_eq!(efficiency.llm_latency_seconds, 4.0);
     }
 }
@@ -2,9 +2,13 @@ use ensobench_evaluator::{config::DomainsConfig, parse::load_transactions, score
 
 #[test]
 fn parses_sample_artifact() {
-    let transactions =
-        load_transactions("docs/examples/core_route/per_tx.jsonl").expect("parse sample artifact");
-    let domains = DomainsConfig::load("dataset/domains.enso.yaml").expect("load domains");
+    let root = concat!(env!("CARGO_MANIFEST_DIR"), "/../..");
+    let transactions = load_transactions(format!("{root}/docs/examples/core_route/per_tx.jsonl"))
+        .expect("parse sample artifact");
+    let mut domains = DomainsConfig::load(format!("{root}/dataset/domains.enso.yaml")).expect("load domains");
+    // The sample was never simulated, so it only counts with `--count-unsimulated`.
+    assert_eq!(score(&transactions, &domains).metadata.total_transactions, 0);
+    domains.count_unsimulated = true;
     let report = score(&transactions, &domains);
     assert!(report.final_score >= 1.0);
 }
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::{
+    artifacts::ArtifactWriter,
+    enso_client::{ActionRequest, BundleRequest},
+    error::RunnerResult,
+    trajectory::StepKind,
+};
+
+use super::tools::record_step;
+
+/// Enso's placeholder address for the chain's native token, which never needs an allowance.
+pub const NATIVE_TOKEN: &str = "0xeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee";
+
+/// Spender used for auto-inserted approves; Enso resolves it to the bundle's `tx.to`.
+pub const DEFAULT_SPENDER: &str = "router";
+
+/// The spender wallet inputs need an allowance for under `routing_strategy`. Router
+/// strategies (and an unset one, treated as `router`) pull tokens through `tx.to`;
+/// delegate/ensowallet strategies run by delegatecall in the wallet and need none.
+pub fn allowance_spender(routing_strategy: Option<&str>) -> Option<&'static str> {
+    match routing_strategy {
+        None | Some("router") | Some("router-legacy") => Some(DEFAULT_SPENDER),
+        Some(_) => None,
+    }
+}
+
+/// What the approve normalization pass changed in a bundle.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ApprovalPatch {
+    pub inserted: Vec<InsertedApprove>,
+    pub removed: Vec<RemovedApprove>,
+}
+
+/// An approve added by the runner; `index` is its position in the patched action list.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InsertedApprove {
+    pub index: usize,
+    pub token: String,
+    pub spender: String,
+    pub before: String,
+}
+
+/// An agent-authored approve that was dropped; `index` is its position in the original list.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RemovedApprove {
+    pub index: usize,
+    pub token: String,
+    pub spender: String,
+    pub reason: String,
+}
+
+impl ApprovalPatch {
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Inserts an approve for the [`allowance_spender`] before every swap/deposit/repay that
+/// pulls a token from the wallet without a prior allowance for it, and drops approves that
+/// can never be needed: duplicates (same token and spender), native-token approves and
+/// approves of tokens the bundle itself produced.
+pub fn normalize_approvals(request: &mut BundleRequest) -> ApprovalPatch {
+    let spender = allowance_spender(request.routing_strategy.as_deref());
+    let mut patch = ApprovalPatch::default();
+    let mut actions = Vec::with_capacity(request.actions.len() + 1);
+    let mut approved = HashSet::new();
+    let mut held = HashSet::new();
+
+    for (index, action) in std::mem::take(&mut request.actions).into_iter().enumerate() {
+        if let ActionRequest::Approve { token, spender } = &action {
+            let key = (token.to_ascii_lowercase(), spender.to_ascii_lowercase());
+            let reason = if key.0 == NATIVE_TOKEN {
+                Some("native token needs no allowance")
+            } else if approved.contains(&key) {
+                Some("duplicate approve")
+            } else if held.contains(&key.0) {
+                Some("token is produced inside the bundle")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                patch.removed.push(RemovedApprove {
+                    index,
+                    token: token.clone(),
+                    spender: spender.clone(),
+                    reason: reason.into(),
+                });
+                continue;
+            }
+            approved.insert(key);
+            actions.push(action);
+            continue;
+        }
+
+        if let (Some((kind, token)), Some(spender)) = (wallet_input(&action), spender) {
+            let key = (token.to_ascii_lowercase(), spender.to_string());
+            if key.0 != NATIVE_TOKEN && !approved.contains(&key) && !held.contains(&key.0) {
+                patch.inserted.push(InsertedApprove {
+                    index: actions.len(),
+                    token: token.to_string(),
+                    spender: spender.into(),
+                    before: kind.into(),
+                });
+                actions.push(ActionRequest::Approve {
+                    token: token.to_string(),
+                    spender: spender.into(),
+                });
+                approved.insert(key);
+            }
+        }
+        if let Some(token) = output(&action) {
+            held.insert(token.to_ascii_lowercase());
+        }
+        actions.push(action);
+    }
+
+    request.actions = actions;
+    patch
+}
+
+/// Normalizes the bundle and, if anything changed, records an `approve_patch` trajectory
+/// step so auto-inserted approves can be told apart from agent-authored ones.
+pub fn patch_bundle(request: &mut BundleRequest, writer: &mut ArtifactWriter) -> RunnerResult<ApprovalPatch> {
+    let patch = normalize_approvals(request);
+    if !patch.is_empty() {
+        tracing::info!(
+            inserted = patch.inserted.len(),
+            removed = patch.removed.len(),
+            "patched bundle approves"
+        );
+        record_step(
+            writer,
+            StepKind::event("approve_patch", serde_json::to_value(&patch)?),
+        )?;
+    }
+    Ok(patch)
+}
+
+/// The token an action pulls from the wallet, if any.
+fn wallet_input(action: &ActionRequest) -> Option<(&'static str, &str)> {
+    match action {
+        ActionRequest::Swap { token_in, .. } => Some(("swap", token_in)),
+        // `amountSource` means the deposit spends the previous action's output.
+        ActionRequest::Deposit {
+            asset,
+            amount_source: None,
+            ..
+        } => Some(("deposit", asset)),
+        ActionRequest::Repay { asset, .. } => Some(("repay", asset)),
+        _ => None,
+    }
+}
+
+/// The token an action leaves with the router for later actions, if any.
+fn output(action: &ActionRequest) -> Option<&str> {
+    match action {
+        ActionRequest::Swap { token_out, .. } => Some(token_out),
+        ActionRequest::Borrow { asset, .. } => Some(asset),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+    fn bundle(actions: Vec<ActionRequest>) -> BundleRequest {
+        BundleRequest {
+            chain_id: 1,
+            actions,
+            recipient: None,
+            routing_strategy: Some("router".into()),
+            extra: Default::default(),
+        }
+    }
+
+    fn approve(token: &str) -> ActionRequest {
+        approve_for(token, "router")
+    }
+
+    fn approve_for(token: &str, spender: &str) -> ActionRequest {
+        ActionRequest::Approve {
+            token: token.into(),
+            spender: spender.into(),
+        }
+    }
+
+    fn swap(token_in: &str, token_out: &str) -> ActionRequest {
+        ActionRequest::Swap {
+            token_in: token_in.into(),
+            token_out: token_out.into(),
+            amount: "1".into(),
+            slippage_bps: Some(30),
+        }
+    }
+
+    fn kinds(request: &BundleRequest) -> Vec<String> {
+        request
+            .actions
+            .iter()
+            .map(|action| {
+                serde_json::to_value(action).unwrap()["action"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inserts_missing_approves_for_wallet_inputs() {
+        let mut request = bundle(vec![
+            swap(USDC, WETH),
+            ActionRequest::Deposit {
+                protocol: "aave_v3".into(),
+                asset: WETH.into(),
+                amount_source: Some("fromPrevious".into()),
+            },
+            ActionRequest::Repay {
+                protocol: "aave_v3".into(),
+                asset: USDC.into(),
+                amount: "1".into(),
+            },
+            ActionRequest::Repay {
+                protocol: "aave_v3".into(),
+                asset: "0xdAC17F958D2ee523a2206206994597C13D831ec7".into(),
+                amount: "1".into(),
+            },
+        ]);
+
+        let patch = normalize_approvals(&mut request);
+        assert_eq!(
+            kinds(&request),
+            ["approve", "swap", "deposit", "repay", "approve", "repay"]
+        );
+        assert_eq!(patch.inserted.len(), 2);
+        assert_eq!(patch.inserted[0].index, 0);
+        assert_eq!(patch.inserted[0].before, "swap");
+        assert_eq!(patch.inserted[1].index, 4);
+        assert!(patch.removed.is_empty());
+    }
+
+    #[test]
+    fn removes_redundant_approves_and_keeps_valid_bundles_untouched() {
+        let mut request = bundle(vec![
+            approve(USDC),
+            approve(&USDC.to_ascii_lowercase()),
+            approve("0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE"),
+            swap(USDC, WETH),
+            approve(WETH),
+        ]);
+        let patch = normalize_approvals(&mut request);
+        assert_eq!(kinds(&request), ["approve", "swap"]);
+        let reasons: Vec<_> = patch
+            .removed
+            .iter()
+            .map(|r| (r.index, r.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (1, "duplicate approve"),
+                (2, "native token needs no allowance"),
+                (4, "token is produced inside the bundle")
+            ]
+        );
+
+        let mut clean = bundle(vec![approve(USDC), swap(USDC, WETH)]);
+        assert!(normalize_approvals(&mut clean).is_empty());
+        assert_eq!(kinds(&clean), ["approve", "swap"]);
+    }
+
+    #[test]
+    fn keys_approves_on_token_and_spender() {
+        let pool = "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640";
+        let mut request = bundle(vec![
+            approve(USDC),
+            approve_for(USDC, pool),
+            approve_for(USDC, pool),
+            swap(USDC, WETH),
+        ]);
+        let patch = normalize_approvals(&mut request);
+        assert_eq!(kinds(&request), ["approve", "approve", "swap"]);
+        assert_eq!(patch.removed.len(), 1);
+        assert_eq!(
+            (patch.removed[0].index, patch.removed[0].spender.as_str()),
+            (2, pool)
+        );
+
+        // Only the pool is approved, so the router still needs its own allowance.
+        let mut request = bundle(vec![approve_for(USDC, pool), swap(USDC, WETH)]);
+        let patch = normalize_approvals(&mut request);
+        assert_eq!(kinds(&request), ["approve", "approve", "swap"]);
+        assert_eq!(patch.inserted[0].spender, "router");
+
+        // Delegate bundles run in the wallet via delegatecall and need no allowance.
+        let mut delegate = bundle(vec![swap(USDC, WETH)]);
+        delegate.routing_strategy = Some("delegate".into());
+        assert!(normalize_approvals(&mut delegate).is_empty());
+    }
+}
@@ -0,0 +1,337 @@
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+use clap::Args as ClapArgs;
+use ensobench_evaluator::{
+    parse::load_transactions,
+    score::{coverage_gaps, score},
+    CoverageGaps, DomainsConfig, ScoreReport,
+};
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::{
+    artifacts::RunContext,
+    config::RunnerConfig,
+    error::{RunnerError, RunnerResult},
+    experiment::SamplingArgs,
+    llm::provider_from_config,
+    redact::Redactor,
+    trajectory::StepKind,
+};
+
+use super::{
+    context::{balance_context, BalanceArgs},
+    plan::{execute_outcome, offline_plan, record_llm_error, request_plan},
+    policy::PlanPolicy,
+    tools::record_step,
+};
+
+/// Covered signatures listed back to the model; older ones are summarised by count.
+const MAX_COVERED_LINES: usize = 20;
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct Args {
+    /// Optional campaign goal override (plain text)
+    #[arg(long)]
+    pub prompt: Option<PathBuf>,
+    /// Domains configuration used to score progress and find gaps
+    #[arg(long, default_value = "dataset/domains.enso.yaml")]
+    pub domains: PathBuf,
+    /// Transaction budget: one plan per iteration
+    #[arg(long, default_value_t = 8)]
+    pub iterations: u32,
+    /// Artifact label
+    #[arg(long, default_value = "campaign")]
+    pub label: String,
+    /// Run each returned tx inside an Anvil fork
+    #[arg(long)]
+    pub simulate: bool,
+    /// Override fork URL; defaults to config per chain-id
+    #[arg(long)]
+    pub fork_url: Option<Url>,
+    /// How many times to re-prompt the model with schema errors before giving up
+    #[arg(long, default_value_t = 2)]
+    pub max_repairs: u32,
+    /// Cycle through stub plans even if an LLM provider is configured
+    #[arg(long)]
+    pub offline: bool,
+    #[command(flatten)]
+    pub sampling: SamplingArgs,
+    #[command(flatten)]
+    pub balances: BalanceArgs,
+}
+
+pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
+    let goal = if let Some(path) = &args.prompt {
+        fs::read_to_string(path)?
+    } else {
+        default_goal()
+    };
+    let mut domains = DomainsConfig::load(&args.domains).map_err(RunnerError::Config)?;
+    // Without a fork nothing is simulated; score the plans rather than report zero.
+    domains.count_unsimulated |= !args.simulate;
+
+    let run_context =
+        RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
+    let per_tx_path = run_context.root.join("per_tx.jsonl");
+    let mut writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
+    let goal =
+        match balance_context(config, &args.balances, &goal, args.fork_url.as_ref(), &mut writer).await? {
+            Some(snapshot) => snapshot.inject(&goal),
+            None => goal,
+        };
+
+    let provider = if args.offline {
+        None
+    } else {
+        provider_from_config(config)?
+    };
+    let policy = PlanPolicy::from_prompt(&goal);
+    let offline = offline_plans();
+    let mut repairs = 0;
+    let mut last_error: Option<String> = None;
+    let mut iterations = 0;
+
+    let stop = loop {
+        let report = score(
+            &load_transactions(&per_tx_path).map_err(RunnerError::Config)?,
+            &domains,
+        );
+        let gaps = coverage_gaps(&report, &domains);
+        record_step(
+            &mut writer,
+            StepKind::event(
+                "campaign",
+                json!({ "iteration": iterations, "score": report.final_score, "gaps": gaps }),
+            ),
+        )?;
+
+        if iterations >= args.iterations {
+            break "budget";
+        }
+        if gaps.is_empty() {
+            break "covered";
+        }
+
+        let prompt = campaign_prompt(
+            &goal,
+            iterations,
+            args.iterations,
+            &report,
+            &gaps,
+            last_error.as_deref(),
+        );
+        let outcome = match &provider {
+            Some(provider) => {
+                match request_plan(
+                    provider.as_ref(),
+                    SYSTEM_PROMPT,
+                    &prompt,
+                    args.max_repairs,
+                    &mut writer,
+                )
+                .await
+                {
+                    Ok(outcome) => outcome,
+                    Err(err) => {
+                        // The iteration is spent either way, so a dead provider cannot loop forever.
+                        last_error = Some(record_llm_error(&mut writer, provider.name(), &err)?);
+                        iterations += 1;
+                        continue;
+                    }
+                }
+            }
+            None => {
+                let fallback = offline[iterations as usize % offline.len()].clone();
+                offline_plan(fallback, &mut writer)?
+            }
+        };
+        repairs += outcome.repairs;
+        iterations += 1;
+
+        // One rejected plan should not end the campaign: record it and tell the model next time.
+        last_error = match execute_outcome(
+            config,
+            &mut writer,
+            &outcome,
+            &policy,
+            args.simulate,
+            args.fork_url.clone(),
+            &args.label,
+        )
+        .await
+        {
+            Ok(note) => note,
+            Err(err) => {
+                tracing::warn!(error = %err, iteration = iterations, "campaign plan was rejected");
+                writer
+                    .append_plan_failure(&outcome.completion, &err.to_string())
+                    .map_err(RunnerError::Config)?;
+                Some(err.to_string())
+            }
+        };
+    };
+
+    let final_score = score(
+        &load_transactions(&per_tx_path).map_err(RunnerError::Config)?,
+        &domains,
+    )
+    .final_score;
+    writer.set_plan_repairs(repairs);
+    let notes = format!("stop: {stop}; iterations: {iterations}; final_score: {final_score:.2}");
+    writer
+        .finalize("campaign", Some(notes))
+        .map_err(RunnerError::Config)?;
+
+    Ok(())
+}
+
+/// Builds the per-iteration prompt: what is already covered and what is still open.
+pub fn campaign_prompt(
+    goal: &str,
+    iteration: u32,
+    budget: u32,
+    report: &ScoreReport,
+    gaps: &CoverageGaps,
+    last_error: Option<&str>,
+) -> String {
+    let mut prompt = format!(
+        "{goal}\n\nTransaction {} of {budget}. Coverage score so far: {:.2} from {} transactions.\n",
+        iteration + 1,
+        report.final_score,
+        report.metadata.total_transactions
+    );
+
+    let covered: Vec<String> = report
+        .unique_sigs
+        .iter()
+        .flat_map(|(domain, sigs)| {
+            sigs.iter().map(move |sig| {
+                format!(
+                    "- {domain}: {} via {} on chain {}",
+                    sig.action,
+                    sig.protocol.as_deref().unwrap_or("?"),
+                    sig.chain_id
+                )
+            })
+        })
+        .collect();
+    if !covered.is_empty() {
+        prompt.push_str("Already covered (repeats score nothing):\n");
+        for line in covered.iter().take(MAX_COVERED_LINES) {
+            let _ = writeln!(prompt, "{line}");
+        }
+        if covered.len() > MAX_COVERED_LINES {
+            let _ = writeln!(prompt, "- ... and {} more", covered.len() - MAX_COVERED_LINES);
+        }
+    }
+
+    if !gaps.uncovered_domains.is_empty() {
+        let _ = writeln!(prompt, "Uncovered domains: {}", gaps.uncovered_domains.join(", "));
+    }
+    for (domain, actions) in &gaps.uncovered_actions {
+        let actions: Vec<String> = actions.iter().map(ToString::to_string).collect();
+        let _ = writeln!(prompt, "Uncovered {domain} actions: {}", actions.join(", "));
+    }
+    for (domain, protocols) in &gaps.uncovered_protocols {
+        let _ = writeln!(prompt, "Uncovered {domain} protocols: {}", protocols.join(", "));
+    }
+    if !gaps.uncovered_chains.is_empty() {
+        let chains: Vec<String> = gaps.uncovered_chains.iter().map(u64::to_string).collect();
+        let _ = writeln!(prompt, "Uncovered chains: {}", chains.join(", "));
+    }
+    if let Some(error) = last_error {
+        let _ = writeln!(prompt, "The previous plan failed: {error}");
+    }
+
+    prompt.push_str("Return one plan that adds as many new action signatures as possible.");
+    prompt
+}
+
+fn default_goal() -> String {
+    "Run an EnsoBench coverage campaign: maximise the number of distinct (chain, action, protocol, tokens) \
+     signatures across domains using Enso Shortcuts."
+        .to_string()
+}
+
+const SYSTEM_PROMPT: &str = "You are an EnsoBench coverage planner. Each turn, pick the plan that covers the most \
+     uncovered domains, protocols and chains. Respond with strict JSON (no prose, no code fences) containing \
+     exactly one executable plan: either {\"route\": {chainId, tokenIn: {address}, tokenOut: {address}, amount, \
+     slippageBps, recipient}} or {\"bundle\": {chainId, actions: [{\"action\": \"approve|swap|deposit|borrow|\
+     repay|stake|harvest|bridge\", ...}], recipient}}.";
+
+/// Stub plans cycled through when no provider is available.
+fn offline_plans() -> Vec<Value> {
+    vec![
+        json!({
+            "route": {
+                "chainId": 1,
+                "tokenIn": { "address": "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" },
+                "tokenOut": { "address": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2" },
+                "amount": "100000000",
+                "slippageBps": 30
+            }
+        }),
+        json!({
+            "bundle": {
+                "chainId": 1,
+                "routingStrategy": "router",
+                "actions": [
+                    { "action": "approve", "token": "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "spender": "router" },
+                    { "action": "swap", "tokenIn": "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                      "tokenOut": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", "amount": "100000000", "slippageBps": 40 },
+                    { "action": "deposit", "protocol": "aave_v3",
+                      "asset": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", "amountSource": "fromPrevious" }
+                ]
+            }
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ensobench_evaluator::{model::ScoreMeta, ActionKind, ActionSig};
+    use indexmap::IndexMap;
+
+    #[test]
+    fn prompt_lists_covered_signatures_and_gaps() {
+        let report = ScoreReport {
+            final_score: 1.0,
+            by_domain: IndexMap::from([("dex".to_string(), 1.0)]),
+            bonus: 0.0,
+            penalty: 0.0,
+            penalties: Vec::new(),
+            unique_sigs: IndexMap::from([(
+                "dex".to_string(),
+                vec![ActionSig::new(
+                    1,
+                    ActionKind::Swap,
+                    Some("uniswap-v3".into()),
+                    None,
+                    None,
+                )],
+            )]),
+            metadata: ScoreMeta {
+                total_transactions: 1,
+                unique_action_signatures: 1,
+            },
+            efficiency: None,
+        };
+        let gaps = CoverageGaps {
+            uncovered_domains: vec!["lending".into()],
+            uncovered_actions: IndexMap::from([("lending".to_string(), vec![ActionKind::Borrow])]),
+            uncovered_protocols: IndexMap::new(),
+            uncovered_chains: vec![8453],
+        };
+
+        let prompt = campaign_prompt("goal", 1, 4, &report, &gaps, Some("HTTP 400"));
+        assert!(prompt.contains("Transaction 2 of 4"));
+        assert!(prompt.contains("- dex: swap via uniswap-v3 on chain 1"));
+        assert!(prompt.contains("Uncovered lending actions: borrow"));
+        assert!(prompt.contains("Uncovered chains: 8453"));
+        assert!(prompt.contains("The previous plan failed: HTTP 400"));
+    }
+}
@@ -0,0 +1,368 @@
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+use clap::{Args as ClapArgs, ValueEnum};
+use once_cell::sync::Lazy;
+use primitive_types::U256;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use url::Url;
+
+use crate::{
+    artifacts::ArtifactWriter,
+    config::RunnerConfig,
+    enso_client::{EnsoClient, WalletBalancesRequest},
+    error::{RunnerError, RunnerResult},
+    trajectory::StepKind,
+    txexec::anvil::rpc_call,
+};
+
+use super::{approvals::NATIVE_TOKEN, tools::record_step};
+
+/// First account of Anvil's default mnemonic; the executor sends simulated txs from it.
+pub const ANVIL_DEFAULT_ACCOUNT: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+/// Chain read by the fork source when `--balance-chain-id` is omitted.
+const DEFAULT_FORK_CHAIN: u64 = 1;
+
+static ADDRESS: Lazy<Regex> = Lazy::new(|| Regex::new(r"0x[0-9a-fA-F]{40}").expect("valid regex"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSource {
+    /// Enso `/api/v1/wallet/balances`
+    Enso,
+    /// `eth_getBalance` / `balanceOf` against the fork RPC, for tokens quoted in the prompt
+    Fork,
+}
+
+impl BalanceSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Enso => "enso",
+            Self::Fork => "fork",
+        }
+    }
+}
+
+/// Prompt context flags shared by the LLM agents.
+#[derive(ClapArgs, Debug, Clone)]
+pub struct BalanceArgs {
+    /// Inject a holdings table for the simulated wallet into the prompt
+    #[arg(long, value_enum)]
+    pub balances: Option<BalanceSource>,
+    /// Wallet whose holdings are shown; defaults to the account that sends simulated txs
+    #[arg(long, default_value = ANVIL_DEFAULT_ACCOUNT)]
+    pub wallet: String,
+    /// Chain to read holdings on (Enso: all chains when omitted; fork: chain 1)
+    #[arg(long)]
+    pub balance_chain_id: Option<u64>,
+}
+
+/// The holdings shown to the agent. Its `id` is stamped on every per_tx line of the run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceSnapshot {
+    pub id: String,
+    pub source: BalanceSource,
+    pub wallet: String,
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+    pub taken_at: DateTime<Utc>,
+    pub holdings: Vec<Holding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Holding {
+    pub chain_id: u64,
+    pub symbol: String,
+    pub address: String,
+    pub decimals: u8,
+    /// Raw integer balance in token units.
+    pub balance: String,
+    #[serde(default)]
+    pub usd_value: Option<f64>,
+}
+
+impl BalanceSnapshot {
+    fn new(source: BalanceSource, wallet: &str, chain_id: Option<u64>, holdings: Vec<Holding>) -> Self {
+        let taken_at = Utc::now();
+        let chain = chain_id.map_or_else(|| "all".to_string(), |id| id.to_string());
+        Self {
+            id: format!("{}-{chain}-{}", source.as_str(), taken_at.format("%Y%m%dT%H%M%S")),
+            source,
+            wallet: wallet.to_string(),
+            chain_id,
+            taken_at,
+            holdings,
+        }
+    }
+
+    /// Markdown holdings table appended to prompts.
+    pub fn render(&self) -> String {
+        let mut table = format!(
+            "Wallet {} holdings (snapshot {}):\n| chain | token | address | balance | raw units | usd |\n|---|---|---|---|---|---|\n",
+            self.wallet, self.id
+        );
+        for holding in &self.holdings {
+            let _ = writeln!(
+                table,
+                "| {} | {} | {} | {} | {} | {} |",
+                holding.chain_id,
+                holding.symbol,
+                holding.address,
+                format_units(&holding.balance, holding.decimals),
+                holding.balance,
+                holding
+                    .usd_value
+                    .map(|usd| format!("{usd:.2}"))
+                    .unwrap_or_else(|| "-".into())
+            );
+        }
+        if self.holdings.is_empty() {
+            table.push_str("| - | (no holdings) | - | 0 | 0 | - |\n");
+        }
+        table
+    }
+
+    /// Returns `prompt` with the holdings table appended.
+    pub fn inject(&self, prompt: &str) -> String {
+        format!(
+            "{prompt}\n\n{}Amounts in plans are raw units; derive percentages of holdings from the raw column.",
+            self.render()
+        )
+    }
+}
+
+/// Takes the snapshot requested by `args`, records it as a `balance_snapshot` trajectory
+/// step and tags the writer so per_tx lines reference it. Failures only warn: the agent
+/// runs without holdings rather than not at all.
+pub async fn balance_context(
+    config: &RunnerConfig,
+    args: &BalanceArgs,
+    prompt: &str,
+    fork_url: Option<&Url>,
+    writer: &mut ArtifactWriter,
+) -> RunnerResult<Option<BalanceSnapshot>> {
+    let Some(source) = args.balances else {
+        return Ok(None);
+    };
+    let snapshot = match source {
+        BalanceSource::Enso => enso_snapshot(config, args).await,
+        BalanceSource::Fork => {
+            let chain_id = args.balance_chain_id.unwrap_or(DEFAULT_FORK_CHAIN);
+            let fork_url = fork_url
+                .cloned()
+                .or_else(|| config.default_fork_urls.get(&chain_id).cloned());
+            match fork_url {
+                Some(url) => fork_snapshot(&url, chain_id, &args.wallet, prompt).await,
+                None => Err(RunnerError::Executor(format!(
+                    "no fork URL configured for chain {chain_id}"
+                ))),
+            }
+        }
+    };
+
+    match snapshot {
+        Ok(snapshot) => {
+            record_step(
+                writer,
+                StepKind::event("balance_snapshot", serde_json::to_value(&snapshot)?),
+            )?;
+            writer.set_balance_snapshot(&snapshot.id);
+            Ok(Some(snapshot))
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, ?source, "could not read wallet balances; prompting without holdings");
+            Ok(None)
+        }
+    }
+}
+
+async fn enso_snapshot(config: &RunnerConfig, args: &BalanceArgs) -> RunnerResult<BalanceSnapshot> {
+    let client = EnsoClient::from_config(config).map_err(RunnerError::Config)?;
+    let response = client
+        .get_wallet_balances(&WalletBalancesRequest {
+            wallet: args.wallet.clone(),
+            use_eoa: Some(true),
+            chain_id: args.balance_chain_id,
+        })
+        .await?;
+    let holdings = response
+        .balances
+        .into_iter()
+        .map(|balance| Holding {
+            chain_id: balance.token.chain_id,
+            symbol: balance.token.symbol,
+            address: balance.token.address,
+            decimals: balance.token.decimals,
+            balance: balance.balance,
+            usd_value: balance.usd_value,
+        })
+        .collect();
+    Ok(BalanceSnapshot::new(
+        BalanceSource::Enso,
+        &args.wallet,
+        args.balance_chain_id,
+        holdings,
+    ))
+}
+
+/// Reads the native balance plus every ERC-20 address quoted in the prompt.
+async fn fork_snapshot(
+    fork_url: &Url,
+    chain_id: u64,
+    wallet: &str,
+    prompt: &str,
+) -> RunnerResult<BalanceSnapshot> {
+    let client = reqwest::Client::new();
+    let url = fork_url.as_str();
+
+    let native = rpc_call(&client, url, "eth_getBalance", json!([wallet, "latest"])).await?;
+    let mut holdings = vec![Holding {
+        chain_id,
+        symbol: "ETH".into(),
+        address: NATIVE_TOKEN.into(),
+        decimals: 18,
+        balance: hex_to_dec(native.as_str().unwrap_or("0x0")),
+        usd_value: None,
+    }];
+
+    let mut seen = Vec::new();
+    for token in ADDRESS.find_iter(prompt).map(|m| m.as_str()) {
+        let key = token.to_ascii_lowercase();
+        if key == wallet.to_ascii_lowercase() || key == NATIVE_TOKEN || seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        // Recipients and other non-token addresses return no data for `decimals()`.
+        let decimals = match eth_call(&client, url, token, "0x313ce567").await {
+            Ok(data) if data.len() >= 66 => word(&data, 0),
+            _ => continue,
+        };
+        let Ok(decimals) = u8::try_from(decimals.low_u64()) else {
+            continue;
+        };
+        let balance = eth_call(
+            &client,
+            url,
+            token,
+            &format!("0x70a08231{:0>64}", wallet.trim_start_matches("0x")),
+        )
+        .await?;
+        let symbol = eth_call(&client, url, token, "0x95d89b41")
+            .await
+            .ok()
+            .and_then(|data| decode_symbol(&data))
+            .unwrap_or_else(|| "?".into());
+        holdings.push(Holding {
+            chain_id,
+            symbol,
+            address: token.to_string(),
+            decimals,
+            balance: word(&balance, 0).to_string(),
+            usd_value: None,
+        });
+    }
+
+    Ok(BalanceSnapshot::new(
+        BalanceSource::Fork,
+        wallet,
+        Some(chain_id),
+        holdings,
+    ))
+}
+
+async fn eth_call(client: &reqwest::Client, url: &str, to: &str, data: &str) -> RunnerResult<String> {
+    let result = rpc_call(
+        client,
+        url,
+        "eth_call",
+        json!([{ "to": to, "data": data }, "latest"]),
+    )
+    .await?;
+    Ok(result.as_str().unwrap_or("0x").to_string())
+}
+
+/// The `index`-th 32-byte word of ABI-encoded return data.
+fn word(data: &str, index: usize) -> U256 {
+    let hex = data.trim_start_matches("0x");
+    hex.get(index * 64..(index + 1) * 64)
+        .and_then(|word| U256::from_str_radix(word, 16).ok())
+        .unwrap_or_default()
+}
+
+/// Decodes `symbol()` as an ABI string, falling back to the bytes32 form some tokens use.
+fn decode_symbol(data: &str) -> Option<String> {
+    let bytes = hex::decode(data.trim_start_matches("0x")).ok()?;
+    let text = if bytes.len() >= 64 && word(data, 0) == U256::from(32) {
+        let len = word(data, 1).low_u64() as usize;
+        bytes.get(64..64 + len)?.to_vec()
+    } else {
+        bytes.get(..32)?.iter().copied().take_while(|b| *b != 0).collect()
+    };
+    String::from_utf8(text).ok().filter(|s| !s.is_empty())
+}
+
+fn hex_to_dec(value: &str) -> String {
+    U256::from_str_radix(value.trim_start_matches("0x"), 16)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Renders a raw integer amount with `decimals` places, trimming trailing zeros.
+pub fn format_units(raw: &str, decimals: u8) -> String {
+    let Ok(value) = U256::from_dec_str(raw) else {
+        return raw.to_string();
+    };
+    let scale = U256::exp10(decimals as usize);
+    let whole = value / scale;
+    let fraction = value % scale;
+    if fraction.is_zero() {
+        return whole.to_string();
+    }
+    let fraction = format!("{:0>width$}", fraction.to_string(), width = decimals as usize);
+    format!("{whole}.{}", fraction.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_holdings_with_decimals() {
+        assert_eq!(format_units("2500000000", 6), "2500");
+        assert_eq!(format_units("1500000000000000000", 18), "1.5");
+        assert_eq!(format_units("not-a-number", 6), "not-a-number");
+
+        let snapshot = BalanceSnapshot::new(
+            BalanceSource::Enso,
+            ANVIL_DEFAULT_ACCOUNT,
+            Some(1),
+            vec![Holding {
+                chain_id: 1,
+                symbol: "USDC".into(),
+                address: "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".into(),
+                decimals: 6,
+                balance: "1000000000".into(),
+                usd_value: Some(1000.0),
+            }],
+        );
+        assert!(snapshot.id.starts_with("enso-1-"));
+        let prompt = snapshot.inject("Bridge 25% of treasuries (USDC).");
+        assert!(prompt.starts_with("Bridge 25%"));
+        assert!(prompt.contains(
+            "| 1 | USDC | 0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48 | 1000 | 1000000000 | 1000.00 |"
+        ));
+    }
+
+    #[test]
+    fn decodes_string_and_bytes32_symbols() {
+        let abi = "0x0000000000000000000000000000000000000000000000000000000000000020\
+                   0000000000000000000000000000000000000000000000000000000000000004\
+                   5553444300000000000000000000000000000000000000000000000000000000";
+        assert_eq!(decode_symbol(abi).as_deref(), Some("USDC"));
+        let bytes32 = "0x4d4b520000000000000000000000000000000000000000000000000000000000";
+        assert_eq!(decode_symbol(bytes32).as_deref(), Some("MKR"));
+    }
+}
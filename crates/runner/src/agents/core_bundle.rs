@@ -8,9 +8,12 @@ use crate::{
     config::RunnerConfig,
     enso_client::{ActionRequest, BundleRequest, EnsoClient},
     error::{RunnerError, RunnerResult},
+    redact::Redactor,
     txexec::{AnvilExecutor, AnvilExecutorConfig},
 };
 
+use super::approvals::patch_bundle;
+
 #[derive(ClapArgs, Debug, Clone)]
 pub struct Args {
     /// Optional path to JSON/YAML bundle scenario describing actions array
@@ -38,8 +41,12 @@ pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
 
     let run_context =
         RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
-    let mut writer = run_context.writer().map_err(RunnerError::Config)?;
+    let mut writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
 
+    let mut request = request;
+    patch_bundle(&mut request, &mut writer)?;
     let response = client.post_bundle(&request).await?;
 
     let execution = if args.simulate {
@@ -72,7 +79,7 @@ pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
     };
 
     writer
-        .append_bundle(&request, &response, execution.as_ref())
+        .append_bundle(&request, &response, execution.as_ref(), &[])
         .map_err(RunnerError::Config)?;
 
     writer
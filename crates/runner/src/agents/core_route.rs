@@ -8,6 +8,7 @@ use crate::{
     config::RunnerConfig,
     enso_client::{EnsoClient, RouteRequest, TokenRef},
     error::{RunnerError, RunnerResult},
+    redact::Redactor,
     txexec::{AnvilExecutor, AnvilExecutorConfig},
 };
 
@@ -38,7 +39,9 @@ pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
 
     let run_context =
         RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
-    let mut writer = run_context.writer().map_err(RunnerError::Config)?;
+    let mut writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
 
     tracing::info!(chain_id = request.chain_id, "requesting /shortcuts/route");
     let response = client.post_route(&request).await?;
@@ -73,7 +76,7 @@ pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
     };
 
     writer
-        .append_route(&request, &response, execution.as_ref())
+        .append_route(&request, &response, execution.as_ref(), &[])
         .map_err(RunnerError::Config)?;
 
     writer.finalize("core_route", None).map_err(RunnerError::Config)?;
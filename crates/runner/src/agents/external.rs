@@ -0,0 +1,317 @@
+use std::{fs, path::PathBuf, process::Stdio, time::Duration};
+
+use clap::Args as ClapArgs;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{ChildStdin, Command},
+    time::timeout,
+};
+use url::Url;
+
+use crate::{
+    artifacts::{ArtifactWriter, RunContext},
+    config::RunnerConfig,
+    error::{RunnerError, RunnerResult},
+    redact::Redactor,
+    trajectory::StepKind,
+};
+
+use super::{
+    policy::PlanPolicy,
+    tools::{record_step, ToolCall, ToolKit},
+};
+
+/// Version of the stdio protocol described in docs/AGENT_PROTOCOL.md.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct Args {
+    /// Executable of the external agent; extra arguments go after `--`
+    #[arg(long)]
+    pub command: PathBuf,
+    /// Arguments passed to the agent process
+    #[arg(last = true)]
+    pub agent_args: Vec<String>,
+    /// Prompt file sent to the agent in the `start` message
+    #[arg(long)]
+    pub prompt: Option<PathBuf>,
+    /// JSON/YAML scenario sent to the agent in the `start` message
+    #[arg(long)]
+    pub scenario: Option<PathBuf>,
+    /// Override fork URL used by the `simulate` tool; defaults to config per chain-id
+    #[arg(long)]
+    pub fork_url: Option<Url>,
+    /// Maximum number of tool calls served before the agent is shut down
+    #[arg(long, default_value_t = 50)]
+    pub max_tool_calls: u32,
+    /// Wall-clock budget for the whole session, in seconds
+    #[arg(long, default_value_t = 600)]
+    pub timeout_secs: u64,
+    /// Artifact label
+    #[arg(long, default_value = "external")]
+    pub label: String,
+}
+
+/// Messages written by the runner to the agent's stdin, one JSON object per line.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunnerMessage {
+    Start {
+        protocol: u32,
+        prompt: Option<String>,
+        scenario: Option<Value>,
+        tools: Vec<Value>,
+    },
+    ToolResult {
+        id: String,
+        name: String,
+        result: Value,
+    },
+    Shutdown {
+        reason: String,
+    },
+}
+
+/// Messages read from the agent's stdout, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentMessage {
+    ToolCall {
+        id: String,
+        name: String,
+        #[serde(default)]
+        arguments: Value,
+    },
+    Log {
+        message: String,
+    },
+    Done {
+        #[serde(default)]
+        summary: Option<String>,
+    },
+}
+
+pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
+    let prompt = args.prompt.as_ref().map(fs::read_to_string).transpose()?;
+    let scenario = args.scenario.as_ref().map(load_scenario).transpose()?;
+
+    let run_context =
+        RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
+    let mut writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
+    // The token map is whatever the agent is handed: prompt text and scenario.
+    let provided = format!(
+        "{} {}",
+        prompt.as_deref().unwrap_or_default(),
+        scenario.as_ref().map(Value::to_string).unwrap_or_default()
+    );
+    let mut toolkit = ToolKit::new(config, args.fork_url.clone(), &args.label)?
+        .with_policy(PlanPolicy::from_prompt(&provided));
+
+    record_step(
+        &mut writer,
+        StepKind::event(
+            "plugin",
+            json!({ "command": args.command.display().to_string(), "args": args.agent_args, "protocol": PROTOCOL_VERSION }),
+        ),
+    )?;
+
+    let mut child = Command::new(&args.command)
+        .args(&args.agent_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| {
+            RunnerError::Executor(format!("failed to spawn agent {}: {err}", args.command.display()))
+        })?;
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let stdout = child.stdout.take().expect("piped stdout");
+
+    let start = RunnerMessage::Start {
+        protocol: PROTOCOL_VERSION,
+        prompt,
+        scenario,
+        tools: ToolKit::definitions(),
+    };
+    send(&mut stdin, &start).await?;
+
+    let session = serve(
+        &mut stdin,
+        BufReader::new(stdout),
+        &mut toolkit,
+        &mut writer,
+        args.max_tool_calls,
+    );
+    let stop = match timeout(Duration::from_secs(args.timeout_secs), session).await {
+        Ok(stop) => stop?,
+        Err(_) => "timeout".to_string(),
+    };
+
+    if stop != "done" && stop != "eof" {
+        // Best effort: the agent may already have exited.
+        let _ = send(&mut stdin, &RunnerMessage::Shutdown { reason: stop.clone() }).await;
+    }
+    drop(stdin);
+    if timeout(Duration::from_secs(5), child.wait()).await.is_err() {
+        tracing::warn!("external agent did not exit after shutdown; killing it");
+        let _ = child.kill().await;
+    }
+
+    let notes = format!("stop: {stop}; unsimulated_quotes: {}", toolkit.unsimulated());
+    writer
+        .finalize("external", Some(notes))
+        .map_err(RunnerError::Config)?;
+    Ok(())
+}
+
+/// Serves tool calls until the agent sends `done`, closes stdout or exhausts the budget.
+async fn serve<R: tokio::io::AsyncBufRead + Unpin>(
+    stdin: &mut ChildStdin,
+    stdout: R,
+    toolkit: &mut ToolKit,
+    writer: &mut ArtifactWriter,
+    max_tool_calls: u32,
+) -> RunnerResult<String> {
+    let mut lines = stdout.lines();
+    let mut calls = 0;
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = match serde_json::from_str::<AgentMessage>(&line) {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::warn!(error = %err, "ignoring malformed agent message");
+                record_step(
+                    writer,
+                    StepKind::event("agent_log", json!({ "raw": line, "error": err.to_string() })),
+                )?;
+                continue;
+            }
+        };
+
+        match message {
+            AgentMessage::ToolCall { id, name, arguments } => {
+                if calls >= max_tool_calls {
+                    return Ok("tool_budget".to_string());
+                }
+                calls += 1;
+                let call = ToolCall { id, name, arguments };
+                let result = toolkit.dispatch(writer, &call, None).await?;
+                let reply = RunnerMessage::ToolResult {
+                    id: call.id,
+                    name: call.name,
+                    result,
+                };
+                send(stdin, &reply).await?;
+            }
+            AgentMessage::Log { message } => {
+                record_step(
+                    writer,
+                    StepKind::event("agent_log", json!({ "message": message })),
+                )?;
+            }
+            AgentMessage::Done { summary } => {
+                record_step(
+                    writer,
+                    StepKind::event("agent_done", json!({ "summary": summary, "tool_calls": calls })),
+                )?;
+                return Ok("done".to_string());
+            }
+        }
+    }
+    Ok("eof".to_string())
+}
+
+async fn send(stdin: &mut ChildStdin, message: &RunnerMessage) -> RunnerResult<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stdin.write_all(&line).await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+fn load_scenario(path: &PathBuf) -> RunnerResult<Value> {
+    let data = fs::read_to_string(path)?;
+    if matches!(path.extension().and_then(|s| s.to_str()), Some("yaml" | "yml")) {
+        Ok(serde_yaml::from_str(&data)?)
+    } else {
+        Ok(serde_json::from_str(&data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_config, test_dir};
+    use crate::trajectory::read_trajectory;
+
+    #[test]
+    fn decodes_agent_messages() {
+        let call: AgentMessage = serde_json::from_str(
+            r#"{"type":"tool_call","id":"1","name":"simulate","arguments":{"quoteId":0}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            call,
+            AgentMessage::ToolCall {
+                id: "1".into(),
+                name: "simulate".into(),
+                arguments: json!({ "quoteId": 0 }),
+            }
+        );
+        let done: AgentMessage = serde_json::from_str(r#"{"type":"done"}"#).unwrap();
+        assert_eq!(done, AgentMessage::Done { summary: None });
+    }
+
+    #[tokio::test]
+    async fn serves_a_scripted_shell_agent() {
+        let dir = test_dir("external");
+        let config = test_config(dir.clone());
+        let script = r#"read start
+echo '{"type":"log","message":"hello"}'
+echo '{"type":"tool_call","id":"c1","name":"teleport","arguments":{}}'
+read result
+echo '{"type":"done","summary":"ok"}'"#;
+        let args = Args {
+            command: "sh".into(),
+            agent_args: vec!["-c".into(), script.into()],
+            prompt: None,
+            scenario: None,
+            fork_url: None,
+            max_tool_calls: 5,
+            timeout_secs: 30,
+            label: "external-test".into(),
+        };
+
+        run(&config, args).await.unwrap();
+
+        let run_dir = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let trajectory = fs::read_to_string(run_dir.join("trajectory.jsonl")).unwrap();
+        let steps = read_trajectory(&trajectory).unwrap();
+        let kinds: Vec<String> = steps
+            .iter()
+            .map(|step| match &step.kind {
+                StepKind::Event { name, .. } => name.clone(),
+                StepKind::ToolCall { .. } => "tool_call".into(),
+                StepKind::ToolResult { .. } => "tool_result".into(),
+                other => panic!("unexpected step {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            ["plugin", "agent_log", "tool_call", "tool_result", "agent_done"]
+        );
+        assert_eq!(steps[3].parent, Some(2));
+        assert!(trajectory.contains("unknown tool `teleport`"));
+        let meta = fs::read_to_string(run_dir.join("meta.json")).unwrap();
+        assert!(meta.contains("stop: done"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
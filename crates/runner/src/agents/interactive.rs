@@ -0,0 +1,441 @@
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use anyhow::anyhow;
+use clap::Args as ClapArgs;
+use ensobench_evaluator::{
+    parse::{read_transactions, ParsedTransaction},
+    score::score,
+    DomainsConfig,
+};
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::{
+    artifacts::{ArtifactWriter, ExecutionRecord, RunArtifact, RunContext},
+    config::RunnerConfig,
+    enso_client::EnsoClient,
+    error::{RunnerError, RunnerResult},
+    redact::Redactor,
+    trajectory::StepKind,
+};
+
+use super::{
+    approvals::{normalize_approvals, ApprovalPatch},
+    plan::{parse_plan, simulate_tx, LlmPlan},
+    policy::{record_violations, PlanPolicy},
+    tools::record_step,
+};
+
+const HELP: &str = "Enter a route/bundle plan in YAML or JSON (shorthand allowed), then an empty line.\n\
+     Commands: :commit  :discard  :score  :simulate on|off  :help  :quit";
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct Args {
+    /// Domains configuration used for the incremental score
+    #[arg(long, default_value = "dataset/domains.enso.yaml")]
+    pub domains: PathBuf,
+    /// Start with fork simulation enabled (toggle with `:simulate on|off`)
+    #[arg(long)]
+    pub simulate: bool,
+    /// Override fork URL; defaults to config per chain-id
+    #[arg(long)]
+    pub fork_url: Option<Url>,
+    /// Artifact label
+    #[arg(long, default_value = "interactive")]
+    pub label: String,
+}
+
+/// A REPL line that is not part of a plan.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Commit,
+    Discard,
+    Score,
+    Simulate(bool),
+    Help,
+    Quit,
+}
+
+pub fn parse_command(line: &str) -> Option<Result<Command, String>> {
+    let line = line.trim();
+    let rest = line.strip_prefix(':')?;
+    let mut parts = rest.split_whitespace();
+    let command = match (parts.next(), parts.next()) {
+        (Some("commit" | "c"), None) => Command::Commit,
+        (Some("discard" | "d"), None) => Command::Discard,
+        (Some("score" | "s"), None) => Command::Score,
+        (Some("simulate"), Some("on")) => Command::Simulate(true),
+        (Some("simulate"), Some("off")) => Command::Simulate(false),
+        (Some("help" | "h"), None) => Command::Help,
+        (Some("quit" | "q" | "exit"), None) => Command::Quit,
+        _ => return Some(Err(format!("unknown command `{line}`; try :help"))),
+    };
+    Some(Ok(command))
+}
+
+/// Parses a YAML or JSON plan block (YAML is a superset of JSON).
+pub fn parse_plan_block(text: &str) -> Result<LlmPlan, String> {
+    let value: Value = serde_yaml::from_str(text).map_err(|err| format!("not valid YAML/JSON: {err}"))?;
+    parse_plan(&value).map_err(|err| err.to_string())
+}
+
+/// A quoted (and possibly simulated) plan awaiting commit or discard.
+struct Pending {
+    plan: LlmPlan,
+    artifact: RunArtifact,
+    approvals: ApprovalPatch,
+    parsed: Vec<ParsedTransaction>,
+}
+
+struct Session {
+    config: RunnerConfig,
+    client: EnsoClient,
+    domains: DomainsConfig,
+    writer: ArtifactWriter,
+    committed: Vec<ParsedTransaction>,
+    pending: Option<Pending>,
+    simulate: bool,
+    fork_url: Option<Url>,
+    label: String,
+    commits: usize,
+}
+
+pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
+    let mut domains = DomainsConfig::load(&args.domains).map_err(RunnerError::Config)?;
+    // `:simulate` can be toggled mid-session, so the live score counts unsimulated commits.
+    domains.count_unsimulated = true;
+    let run_context =
+        RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
+    let writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
+
+    let mut session = Session {
+        config: config.clone(),
+        client: EnsoClient::from_config(config).map_err(RunnerError::Config)?,
+        domains,
+        writer,
+        committed: Vec::new(),
+        pending: None,
+        simulate: args.simulate,
+        fork_url: args.fork_url,
+        label: args.label,
+        commits: 0,
+    };
+
+    println!("EnsoBench interactive session → {}", run_context.root.display());
+    println!("{HELP}");
+
+    let stdin = io::stdin();
+    let mut block = String::new();
+    loop {
+        prompt(if block.is_empty() { "plan> " } else { "....> " })?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+
+        if block.is_empty() {
+            match parse_command(&line) {
+                Some(Ok(Command::Quit)) => break,
+                Some(Ok(command)) => {
+                    session.command(command)?;
+                    continue;
+                }
+                Some(Err(err)) => {
+                    println!("{err}");
+                    continue;
+                }
+                None => {}
+            }
+        }
+
+        if line.trim().is_empty() {
+            if !block.trim().is_empty() {
+                session.submit(&block).await?;
+            }
+            block.clear();
+        } else {
+            block.push_str(&line);
+        }
+    }
+
+    if session.pending.is_some() {
+        println!("Discarding uncommitted step.");
+    }
+    let notes = format!("interactive session; committed {} steps", session.commits);
+    session
+        .writer
+        .finalize("interactive", Some(notes))
+        .map_err(RunnerError::Config)?;
+    Ok(())
+}
+
+impl Session {
+    fn command(&mut self, command: Command) -> RunnerResult<()> {
+        match command {
+            Command::Commit => self.commit()?,
+            Command::Discard => match self.pending.take() {
+                Some(pending) => {
+                    record_step(
+                        &mut self.writer,
+                        StepKind::event(
+                            "interactive",
+                            json!({ "action": "discard", "plan": pending.plan.to_value()? }),
+                        ),
+                    )?;
+                    println!("Discarded.");
+                }
+                None => println!("Nothing to discard."),
+            },
+            Command::Score => {
+                let report = score(&self.committed, &self.domains);
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            Command::Simulate(enabled) => {
+                self.simulate = enabled;
+                println!("Simulation {}.", if enabled { "on" } else { "off" });
+            }
+            Command::Help => println!("{HELP}"),
+            Command::Quit => {}
+        }
+        Ok(())
+    }
+
+    async fn submit(&mut self, block: &str) -> RunnerResult<()> {
+        if self.pending.is_some() {
+            println!("Replacing the uncommitted step.");
+        }
+        let plan = match parse_plan_block(block) {
+            Ok(plan) => plan,
+            Err(err) => {
+                println!("Invalid plan: {err}");
+                return Ok(());
+            }
+        };
+
+        let (artifact, approvals) = match self.quote(&plan).await {
+            Ok(quoted) => quoted,
+            Err(err) => {
+                println!("Enso rejected the plan: {err}");
+                return Ok(());
+            }
+        };
+        if !approvals.is_empty() {
+            println!("Patched approves:\n{}", serde_json::to_string_pretty(&approvals)?);
+        }
+        print_artifact(&artifact)?;
+
+        let parsed =
+            read_transactions(serde_json::to_string(&artifact)?.as_bytes()).map_err(RunnerError::Config)?;
+        let before = score(&self.committed, &self.domains);
+        let mut with_step = self.committed.clone();
+        with_step.extend(parsed.iter().cloned());
+        let after = score(&with_step, &self.domains);
+        println!(
+            "Coverage: {:.2} → {:.2} ({:+.2}), unique signatures {} → {}",
+            before.final_score,
+            after.final_score,
+            after.final_score - before.final_score,
+            before.metadata.unique_action_signatures,
+            after.metadata.unique_action_signatures
+        );
+        println!(":commit to keep this step, :discard to drop it.");
+
+        self.pending = Some(Pending {
+            plan,
+            artifact,
+            approvals,
+            parsed,
+        });
+        Ok(())
+    }
+
+    async fn quote(&self, plan: &LlmPlan) -> RunnerResult<(RunArtifact, ApprovalPatch)> {
+        let fork_url = self
+            .fork_url
+            .clone()
+            .or_else(|| self.config.default_fork_urls.get(&plan.chain_id()).cloned());
+        let timestamp = chrono::Utc::now();
+        let policy_violations = PlanPolicy::default().check(plan);
+
+        Ok(match plan {
+            LlmPlan::Route(request) => {
+                let response = self.client.post_route(request).await?;
+                let execution = self
+                    .maybe_simulate(request.chain_id, fork_url, &response.tx)
+                    .await;
+                let artifact = RunArtifact::Route {
+                    intent_id: None,
+                    timestamp,
+                    request: request.clone(),
+                    response,
+                    execution,
+                    policy_violations,
+                    balance_snapshot: None,
+                };
+                (artifact, ApprovalPatch::default())
+            }
+            LlmPlan::Bundle(request) => {
+                let mut request = request.clone();
+                let approvals = normalize_approvals(&mut request);
+                let response = self.client.post_bundle(&request).await?;
+                let execution = self
+                    .maybe_simulate(request.chain_id, fork_url, &response.tx)
+                    .await;
+                let artifact = RunArtifact::Bundle {
+                    intent_id: None,
+                    timestamp,
+                    request,
+                    response,
+                    execution,
+                    policy_violations,
+                    balance_snapshot: None,
+                };
+                (artifact, approvals)
+            }
+        })
+    }
+
+    async fn maybe_simulate(
+        &self,
+        chain_id: u64,
+        fork_url: Option<Url>,
+        envelope: &crate::enso_client::TransactionEnvelope,
+    ) -> Option<ExecutionRecord> {
+        if self.simulate {
+            Some(simulate_tx(chain_id, fork_url, envelope, &self.label).await)
+        } else {
+            None
+        }
+    }
+
+    fn commit(&mut self) -> RunnerResult<()> {
+        let Some(pending) = self.pending.take() else {
+            println!("Nothing to commit.");
+            return Ok(());
+        };
+
+        // Same trajectory/per_tx shape as the automated agents, so the run can be replayed and scored.
+        record_step(
+            &mut self.writer,
+            StepKind::Plan {
+                plan: pending.plan.to_value()?,
+            },
+        )?;
+        if !pending.approvals.is_empty() {
+            record_step(
+                &mut self.writer,
+                StepKind::event("approve_patch", serde_json::to_value(&pending.approvals)?),
+            )?;
+        }
+        match &pending.artifact {
+            RunArtifact::Route {
+                request,
+                response,
+                execution,
+                policy_violations,
+                ..
+            } => {
+                record_violations(&mut self.writer, policy_violations)?;
+                self.writer
+                    .append_route(request, response, execution.as_ref(), policy_violations)
+            }
+            RunArtifact::Bundle {
+                request,
+                response,
+                execution,
+                policy_violations,
+                ..
+            } => {
+                record_violations(&mut self.writer, policy_violations)?;
+                self.writer
+                    .append_bundle(request, response, execution.as_ref(), policy_violations)
+            }
+            RunArtifact::PlanFailure { .. } => Err(anyhow!("plan failures are never pending")),
+        }
+        .map_err(RunnerError::Config)?;
+
+        self.committed.extend(pending.parsed);
+        self.commits += 1;
+        let report = score(&self.committed, &self.domains);
+        println!(
+            "Committed step {}. Coverage score: {:.2}",
+            self.commits, report.final_score
+        );
+        Ok(())
+    }
+}
+
+fn print_artifact(artifact: &RunArtifact) -> RunnerResult<()> {
+    let (response, execution, violations) = match artifact {
+        RunArtifact::Route {
+            response,
+            execution,
+            policy_violations,
+            ..
+        } => (serde_json::to_value(response)?, execution, policy_violations),
+        RunArtifact::Bundle {
+            response,
+            execution,
+            policy_violations,
+            ..
+        } => (serde_json::to_value(response)?, execution, policy_violations),
+        RunArtifact::PlanFailure { .. } => return Ok(()),
+    };
+    println!("Enso response:\n{}", serde_json::to_string_pretty(&response)?);
+    for violation in violations {
+        println!("Policy violation: {}", violation.message);
+    }
+
+    match execution {
+        Some(record) => {
+            println!(
+                "Simulation: {:?}, gas used {}, tx {}",
+                record.status,
+                record
+                    .gas_used
+                    .map(|gas| gas.to_string())
+                    .unwrap_or_else(|| "-".into()),
+                record.transaction_hash.as_deref().unwrap_or("-")
+            );
+            for log in &record.logs {
+                println!("  log {log}");
+            }
+            for trace in &record.traces {
+                println!("  trace #{} {}", trace.step, trace.detail);
+            }
+        }
+        None => println!("Simulation: off"),
+    }
+    Ok(())
+}
+
+fn prompt(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_yaml_bundle_shorthand_and_commands() {
+        let block = "bundle:\n  chainId: 1\n  actions:\n    - swap: { tokenIn: USDC, tokenOut: WETH, amount: \"1\" }\n";
+        assert!(matches!(parse_plan_block(block), Ok(LlmPlan::Bundle(b)) if b.actions.len() == 1));
+        assert!(parse_plan_block("route: {chainId: 1}").is_err());
+
+        assert_eq!(parse_command(":commit"), Some(Ok(Command::Commit)));
+        assert_eq!(
+            parse_command(" :simulate off\n"),
+            Some(Ok(Command::Simulate(false)))
+        );
+        assert!(matches!(parse_command(":bogus"), Some(Err(_))));
+        assert_eq!(parse_command("route:"), None);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{fs, path::PathBuf};
+
+use clap::Args as ClapArgs;
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::{
+    artifacts::{ArtifactWriter, RunContext},
+    config::RunnerConfig,
+    error::{RunnerError, RunnerResult},
+    experiment::SamplingArgs,
+    llm::{provider_from_config, ChatRequest, LlmProvider},
+    redact::Redactor,
+    trajectory::StepKind,
+};
+
+use super::{
+    context::{balance_context, BalanceArgs},
+    plan::chat,
+    policy::PlanPolicy,
+    tools::{record_step, ToolCall, ToolKit},
+};
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct Args {
+    /// Optional prompt override file (plain text)
+    #[arg(long)]
+    pub prompt: Option<PathBuf>,
+    /// Artifact label
+    #[arg(long, default_value = "llm-agent")]
+    pub label: String,
+    /// Override fork URL used by the `simulate` tool; defaults to config per chain-id
+    #[arg(long)]
+    pub fork_url: Option<Url>,
+    /// Maximum number of model turns
+    #[arg(long, default_value_t = 12)]
+    pub max_steps: u32,
+    /// Stop once the reported total token usage reaches this budget
+    #[arg(long, default_value_t = 60_000)]
+    pub max_tokens: u64,
+    /// Run a scripted quote→simulate sequence instead of calling the model
+    #[arg(long)]
+    pub offline: bool,
+    #[command(flatten)]
+    pub sampling: SamplingArgs,
+    #[command(flatten)]
+    pub balances: BalanceArgs,
+}
+
+pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
+    let prompt = if let Some(path) = &args.prompt {
+        fs::read_to_string(path)?
+    } else {
+        default_prompt()
+    };
+
+    let run_context =
+        RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
+    let mut writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
+    let prompt = match balance_context(
+        config,
+        &args.balances,
+        &prompt,
+        args.fork_url.as_ref(),
+        &mut writer,
+    )
+    .await?
+    {
+        Some(snapshot) => snapshot.inject(&prompt),
+        None => prompt,
+    };
+    let mut toolkit = ToolKit::new(config, args.fork_url.clone(), &args.label)?
+        .with_policy(PlanPolicy::from_prompt(&prompt));
+
+    let provider = if args.offline {
+        None
+    } else {
+        provider_from_config(config)?
+    };
+    let notes = match provider {
+        Some(provider) => agent_loop(provider.as_ref(), &prompt, &args, &mut toolkit, &mut writer).await?,
+        None => offline_script(&mut toolkit, &mut writer).await?,
+    };
+    let notes = format!("{notes}; unsimulated_quotes: {}", toolkit.unsimulated());
+
+    writer
+        .finalize("llm_agent", Some(notes))
+        .map_err(RunnerError::Config)?;
+
+    Ok(())
+}
+
+async fn agent_loop(
+    provider: &dyn LlmProvider,
+    prompt: &str,
+    args: &Args,
+    toolkit: &mut ToolKit,
+    writer: &mut ArtifactWriter,
+) -> RunnerResult<String> {
+    let mut request = ChatRequest {
+        messages: vec![
+            json!({ "role": "system", "content": SYSTEM_PROMPT }),
+            json!({ "role": "user", "content": prompt }),
+        ],
+        tools: ToolKit::definitions(),
+        json_mode: false,
+    };
+    record_step(
+        writer,
+        StepKind::SystemPrompt {
+            text: SYSTEM_PROMPT.into(),
+        },
+    )?;
+    let mut parent = record_step(writer, StepKind::UserPrompt { text: prompt.into() })?;
+    let mut steps = 0;
+    let mut tokens = 0;
+
+    let stop = loop {
+        if steps >= args.max_steps {
+            break "step_budget";
+        }
+        if tokens >= args.max_tokens {
+            break "token_budget";
+        }
+
+        let (completion, step) = chat(provider, &request, writer, Some(parent)).await?;
+        parent = step;
+        steps += 1;
+        tokens += completion
+            .pointer("/usage/total_tokens")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+
+        let message = completion
+            .pointer("/choices/0/message")
+            .cloned()
+            .ok_or_else(|| RunnerError::Llm("completion without a message".into()))?;
+        request.messages.push(message.clone());
+
+        let calls = tool_calls(&message);
+        if calls.is_empty() {
+            break "finished";
+        }
+        for call in calls {
+            let result = toolkit.dispatch(writer, &call, Some(step)).await?;
+            request.messages.push(json!({
+                "role": "tool",
+                "tool_call_id": call.id,
+                "content": result.to_string(),
+            }));
+        }
+    };
+
+    tracing::info!(stop, steps, tokens, "agent loop finished");
+    Ok(format!("stop: {stop}; steps: {steps}; tokens: {tokens}"))
+}
+
+/// Offline fallback that exercises the same tools: quote the stub route and simulate it.
+async fn offline_script(toolkit: &mut ToolKit, writer: &mut ArtifactWriter) -> RunnerResult<String> {
+    let script = [
+        ToolCall {
+            id: "offline-0".into(),
+            name: "post_route".into(),
+            arguments: json!({
+                "chainId": 1,
+                "tokenIn": { "address": "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" },
+                "tokenOut": { "address": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2" },
+                "amount": "100000000",
+                "slippageBps": 30
+            }),
+        },
+        ToolCall {
+            id: "offline-1".into(),
+            name: "simulate".into(),
+            arguments: json!({ "quoteId": 0 }),
+        },
+    ];
+    for call in &script {
+        toolkit.dispatch(writer, call, None).await?;
+    }
+    Ok("stop: offline".to_string())
+}
+
+/// Extracts `tool_calls` from an assistant message. Arguments arrive as a JSON
+/// string; unparseable arguments are passed through as the raw string.
+pub fn tool_calls(message: &Value) -> Vec<ToolCall> {
+    let Some(calls) = message.get("tool_calls").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    calls
+        .iter()
+        .filter_map(|call| {
+            let id = call.get("id").and_then(Value::as_str)?.to_string();
+            let name = call
+                .pointer("/function/name")
+                .and_then(Value::as_str)?
+                .to_string();
+            let arguments = match call.pointer("/function/arguments") {
+                Some(Value::String(raw)) => {
+                    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone()))
+                }
+                Some(other) => other.clone(),
+                None => json!({}),
+            };
+            Some(ToolCall { id, name, arguments })
+        })
+        .collect()
+}
+
+fn default_prompt() -> String {
+    "Maximise EnsoBench coverage on chain 1: discover tokens, quote a swap and a lending bundle, \
+     simulate each quote and retry with a different plan when a simulation reverts."
+        .to_string()
+}
+
+const SYSTEM_PROMPT: &str =
+    "You are an EnsoBench coverage agent. Use the tools to look up tokens and balances, \
+     quote plans with post_route / post_bundle, and call simulate on every quote you want counted. Inspect \
+     simulation results and adjust failing plans. Reply without tool calls when you are done.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_tool_calls_with_string_arguments() {
+        let message = json!({
+            "role": "assistant",
+            "content": null,
+            "tool_calls": [
+                { "id": "call_1", "type": "function",
+                  "function": { "name": "simulate", "arguments": "{\"quoteId\":0}" } },
+                { "id": "call_2", "type": "function",
+                  "function": { "name": "get_tokens", "arguments": "not json" } }
+            ]
+        });
+        let calls = tool_calls(&message);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].arguments, json!({ "quoteId": 0 }));
+        assert_eq!(calls[1].arguments, json!("not json"));
+        assert!(tool_calls(&json!({ "content": "done" })).is_empty());
+    }
+}
@@ -2,11 +2,21 @@ use std::{fs, path::PathBuf};
 
 use clap::Args as ClapArgs;
 use serde_json::json;
+use url::Url;
 
 use crate::{
-    artifacts::{RunContext, TrajectoryStep},
+    artifacts::RunContext,
     config::RunnerConfig,
     error::{RunnerError, RunnerResult},
+    experiment::SamplingArgs,
+    llm::provider_from_config,
+    redact::Redactor,
+};
+
+use super::{
+    context::{balance_context, BalanceArgs},
+    plan::{execute_outcome, offline_plan, record_llm_error, request_plan},
+    policy::PlanPolicy,
 };
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -17,9 +27,22 @@ pub struct Args {
     /// Artifact label
     #[arg(long, default_value = "llm-core")]
     pub label: String,
-    /// Use stubbed planner even if OPENROUTER_API_KEY is present
+    /// Run the returned tx inside an Anvil fork
+    #[arg(long)]
+    pub simulate: bool,
+    /// Override fork URL; defaults to config per chain-id
+    #[arg(long)]
+    pub fork_url: Option<Url>,
+    /// How many times to re-prompt the model with schema errors before giving up
+    #[arg(long, default_value_t = 2)]
+    pub max_repairs: u32,
+    /// Use stubbed planner even if an LLM provider is configured
     #[arg(long)]
     pub offline: bool,
+    #[command(flatten)]
+    pub sampling: SamplingArgs,
+    #[command(flatten)]
+    pub balances: BalanceArgs,
 }
 
 pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
@@ -31,79 +54,76 @@ pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
 
     let run_context =
         RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
-    let mut writer = run_context.writer().map_err(RunnerError::Config)?;
+    let mut writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
+    let prompt = match balance_context(
+        config,
+        &args.balances,
+        &prompt,
+        args.fork_url.as_ref(),
+        &mut writer,
+    )
+    .await?
+    {
+        Some(snapshot) => snapshot.inject(&prompt),
+        None => prompt,
+    };
 
-    let plan_value = if !args.offline {
-        if let Some(api_key) = &config.openrouter_api_key {
-            match request_openrouter(api_key, &prompt, SYSTEM_PROMPT).await {
-                Ok(value) => value,
+    let provider = if args.offline {
+        None
+    } else {
+        provider_from_config(config)?
+    };
+    let outcome = match provider {
+        Some(provider) => {
+            match request_plan(
+                provider.as_ref(),
+                SYSTEM_PROMPT,
+                &prompt,
+                args.max_repairs,
+                &mut writer,
+            )
+            .await
+            {
+                Ok(outcome) => outcome,
                 Err(err) => {
-                    tracing::warn!(error = %err, "OpenRouter call failed; falling back to offline plan");
-                    stub_plan()
+                    let note = record_llm_error(&mut writer, provider.name(), &err)?;
+                    writer
+                        .finalize("llm_core", Some(note))
+                        .map_err(RunnerError::Config)?;
+                    return Ok(());
                 }
             }
-        } else {
-            stub_plan()
         }
-    } else {
-        stub_plan()
+        None => offline_plan(stub_plan(), &mut writer)?,
     };
+    writer.set_plan_repairs(outcome.repairs);
 
-    let step = TrajectoryStep {
-        timestamp: chrono::Utc::now(),
-        role: "planner".into(),
-        content: plan_value,
-    };
-
-    writer
-        .append_trajectory_step(&step)
-        .map_err(RunnerError::Config)?;
+    let notes = execute_outcome(
+        config,
+        &mut writer,
+        &outcome,
+        &PlanPolicy::from_prompt(&prompt),
+        args.simulate,
+        args.fork_url,
+        &args.label,
+    )
+    .await?;
 
-    writer.finalize("llm_core", None).map_err(RunnerError::Config)?;
+    writer.finalize("llm_core", notes).map_err(RunnerError::Config)?;
 
     Ok(())
 }
 
-pub(crate) async fn request_openrouter(
-    api_key: &str,
-    prompt: &str,
-    system_prompt: &str,
-) -> RunnerResult<serde_json::Value> {
-    let client = reqwest::Client::new();
-    let body = json!({
-        "model": "openai/gpt-4.1-mini",
-        "messages": [
-            { "role": "system", "content": system_prompt },
-            { "role": "user", "content": prompt }
-        ],
-        "response_format": { "type": "json_object" }
-    });
-
-    let response = client
-        .post("https://openrouter.ai/api/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("HTTP-Referer", "https://github.com/enso-org/ensobench")
-        .header("X-Title", "EnsoBench Runner")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|err| RunnerError::Llm(format!("send error: {err}")))?
-        .error_for_status()
-        .map_err(|err| RunnerError::Llm(format!("OpenRouter HTTP error: {err}")))?;
-
-    let value: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|err| RunnerError::Llm(format!("decode error: {err}")))?;
-
-    Ok(value)
-}
-
 fn default_prompt() -> String {
     "Plan a coverage transaction using Enso Shortcuts that swaps 100 USDC to WETH on chain 1.".to_string()
 }
 
-const SYSTEM_PROMPT: &str = "You are an EnsoBench planner. Respond with strict JSON.";
+const SYSTEM_PROMPT: &str = "You are an EnsoBench planner. Respond with strict JSON (no prose, no code fences) \
+     containing exactly one executable plan: either {\"route\": {chainId, tokenIn: {address}, tokenOut: {address}, \
+     amount, slippageBps, recipient}} or {\"bundle\": {chainId, actions: [{\"action\": \"approve|swap|deposit|\
+     borrow|repay|stake|harvest|bridge\", ...}], recipient}}.";
 
 fn stub_plan() -> serde_json::Value {
     json!({
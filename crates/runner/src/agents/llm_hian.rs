@@ -2,14 +2,22 @@ use std::{fs, path::PathBuf};
 
 use clap::Args as ClapArgs;
 use serde_json::json;
+use url::Url;
 
 use crate::{
-    artifacts::{RunContext, TrajectoryStep},
+    artifacts::RunContext,
     config::RunnerConfig,
     error::{RunnerError, RunnerResult},
+    experiment::SamplingArgs,
+    llm::provider_from_config,
+    redact::Redactor,
 };
 
-use super::llm_core::request_openrouter;
+use super::{
+    context::{balance_context, BalanceArgs},
+    plan::{execute_outcome, offline_plan, record_llm_error, request_plan},
+    policy::PlanPolicy,
+};
 
 #[derive(ClapArgs, Debug, Clone)]
 pub struct Args {
@@ -22,6 +30,19 @@ pub struct Args {
     /// Artifact label
     #[arg(long, default_value = "llm-hian")]
     pub label: String,
+    /// Run the returned tx inside an Anvil fork
+    #[arg(long)]
+    pub simulate: bool,
+    /// Override fork URL; defaults to config per chain-id
+    #[arg(long)]
+    pub fork_url: Option<Url>,
+    /// How many times to re-prompt the model with schema errors before giving up
+    #[arg(long, default_value_t = 2)]
+    pub max_repairs: u32,
+    #[command(flatten)]
+    pub sampling: SamplingArgs,
+    #[command(flatten)]
+    pub balances: BalanceArgs,
 }
 
 pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
@@ -33,35 +54,64 @@ pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
 
     let run_context =
         RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
-    let mut writer = run_context.writer().map_err(RunnerError::Config)?;
+    let mut writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
+    let prompt = match balance_context(
+        config,
+        &args.balances,
+        &prompt,
+        args.fork_url.as_ref(),
+        &mut writer,
+    )
+    .await?
+    {
+        Some(snapshot) => snapshot.inject(&prompt),
+        None => prompt,
+    };
 
-    let plan_value = if !args.offline {
-        if let Some(api_key) = &config.openrouter_api_key {
-            match request_openrouter(api_key, &prompt, HIAN_SYSTEM_PROMPT).await {
-                Ok(value) => value,
+    let provider = if args.offline {
+        None
+    } else {
+        provider_from_config(config)?
+    };
+    let outcome = match provider {
+        Some(provider) => {
+            match request_plan(
+                provider.as_ref(),
+                HIAN_SYSTEM_PROMPT,
+                &prompt,
+                args.max_repairs,
+                &mut writer,
+            )
+            .await
+            {
+                Ok(outcome) => outcome,
                 Err(err) => {
-                    tracing::warn!(error = %err, "OpenRouter call failed; using offline fallback");
-                    fallback_response()
+                    let note = record_llm_error(&mut writer, provider.name(), &err)?;
+                    writer
+                        .finalize("llm_hian", Some(note))
+                        .map_err(RunnerError::Config)?;
+                    return Ok(());
                 }
             }
-        } else {
-            fallback_response()
         }
-    } else {
-        fallback_response()
+        None => offline_plan(fallback_response(), &mut writer)?,
     };
+    writer.set_plan_repairs(outcome.repairs);
 
-    let step = TrajectoryStep {
-        timestamp: chrono::Utc::now(),
-        role: "planner".into(),
-        content: plan_value,
-    };
-
-    writer
-        .append_trajectory_step(&step)
-        .map_err(RunnerError::Config)?;
+    let notes = execute_outcome(
+        config,
+        &mut writer,
+        &outcome,
+        &PlanPolicy::from_prompt(&prompt),
+        args.simulate,
+        args.fork_url,
+        &args.label,
+    )
+    .await?;
 
-    writer.finalize("llm_hian", None).map_err(RunnerError::Config)?;
+    writer.finalize("llm_hian", notes).map_err(RunnerError::Config)?;
 
     Ok(())
 }
@@ -90,4 +140,6 @@ fn fallback_response() -> serde_json::Value {
     })
 }
 
-const HIAN_SYSTEM_PROMPT: &str = "You are an EnsoBench Operation-Needle agent. Return strict JSON only.";
+const HIAN_SYSTEM_PROMPT: &str = "You are an EnsoBench Operation-Needle agent. Execute the single highest-priority \
+     instruction and return strict JSON only: either {\"route\": {chainId, tokenIn: {address}, tokenOut: {address}, \
+     amount, slippageBps, recipient}} or {\"bundle\": {chainId, actions: [...], recipient}}.";
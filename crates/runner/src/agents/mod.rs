@@ -1,4 +1,15 @@
+pub mod approvals;
+pub mod campaign;
+pub mod context;
 pub mod core_bundle;
 pub mod core_route;
+pub mod external;
+pub mod interactive;
+pub mod llm_agent;
 pub mod llm_core;
 pub mod llm_hian;
+pub mod plan;
+pub mod policy;
+pub mod replay;
+pub mod schema;
+pub mod tools;
@@ -0,0 +1,563 @@
+use std::{future::Future, time::Instant};
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    artifacts::{ArtifactWriter, ExecutionRecord, ExecutionStatus},
+    config::RunnerConfig,
+    enso_client::{BundleRequest, EnsoClient, RouteRequest, TransactionEnvelope},
+    error::{RunnerError, RunnerResult},
+    llm::{ChatRequest, LlmProvider},
+    trajectory::{StepKind, TokenUsage},
+    txexec::{AnvilExecutor, AnvilExecutorConfig},
+};
+
+use super::{
+    approvals::patch_bundle,
+    policy::{record_violations, PlanPolicy},
+    schema::validate_plan,
+    tools::{record_child, record_step},
+};
+
+/// A single executable plan produced by an LLM agent (SPEC §8 vocabulary).
+#[derive(Debug, Clone)]
+pub enum LlmPlan {
+    Route(RouteRequest),
+    Bundle(BundleRequest),
+}
+
+impl LlmPlan {
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            LlmPlan::Route(request) => request.chain_id,
+            LlmPlan::Bundle(request) => request.chain_id,
+        }
+    }
+
+    /// The plan in its `{ "route": ... }` / `{ "bundle": ... }` JSON form.
+    pub fn to_value(&self) -> serde_json::Result<Value> {
+        Ok(match self {
+            LlmPlan::Route(request) => json!({ "route": serde_json::to_value(request)? }),
+            LlmPlan::Bundle(request) => json!({ "bundle": serde_json::to_value(request)? }),
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PlanError {
+    #[error("completion has no message content")]
+    MissingContent,
+    #[error("plan is not valid JSON: {0}")]
+    InvalidJson(#[source] serde_json::Error),
+    #[error("plan must be an object with exactly one of `route` or `bundle`")]
+    UnknownShape,
+    #[error("plan failed schema validation: {}", .0.join("; "))]
+    Schema(Vec<String>),
+    #[error("invalid route plan: {0}")]
+    InvalidRoute(#[source] serde_json::Error),
+    #[error("invalid bundle plan: {0}")]
+    InvalidBundle(#[source] serde_json::Error),
+}
+
+/// Parses either a raw chat completion or an already-extracted plan object and
+/// validates it against [`PLAN_SCHEMA`](super::schema::PLAN_SCHEMA).
+pub fn parse_plan(value: &Value) -> Result<LlmPlan, PlanError> {
+    let mut plan = plan_json(value)?;
+    let object = plan.as_object_mut().ok_or(PlanError::UnknownShape)?;
+    if !object.contains_key("route") && !object.contains_key("bundle") {
+        return Err(PlanError::UnknownShape);
+    }
+    if let Some(bundle) = object.get_mut("bundle") {
+        normalize_bundle(bundle);
+    }
+
+    let errors = validate_plan(&plan);
+    if !errors.is_empty() {
+        return Err(PlanError::Schema(errors));
+    }
+
+    match (plan.get("route"), plan.get("bundle")) {
+        (Some(route), None) => serde_json::from_value(route.clone())
+            .map(LlmPlan::Route)
+            .map_err(PlanError::InvalidRoute),
+        (None, Some(bundle)) => serde_json::from_value(bundle.clone())
+            .map(LlmPlan::Bundle)
+            .map_err(PlanError::InvalidBundle),
+        _ => Err(PlanError::UnknownShape),
+    }
+}
+
+/// Returns the plan JSON carried by a completion, or the value itself when it is
+/// not a chat completion (offline stubs hand over plans directly).
+pub fn plan_json(value: &Value) -> Result<Value, PlanError> {
+    if value.get("choices").is_none() {
+        return Ok(value.clone());
+    }
+
+    let content = value
+        .pointer("/choices/0/message/content")
+        .and_then(Value::as_str)
+        .ok_or(PlanError::MissingContent)?;
+
+    serde_json::from_str(strip_fences(content)).map_err(PlanError::InvalidJson)
+}
+
+fn strip_fences(content: &str) -> &str {
+    let trimmed = content.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let rest = rest.strip_prefix("json").unwrap_or(rest);
+    rest.strip_suffix("```").unwrap_or(rest).trim()
+}
+
+/// Accepts the SPEC's `{ "swap": { ... } }` action shorthand alongside the
+/// internally tagged `{ "action": "swap", ... }` form used by `ActionRequest`,
+/// and camel-cases snake_case action fields so the schema sees one spelling.
+fn normalize_bundle(bundle: &mut Value) {
+    let Some(actions) = bundle.get_mut("actions").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for action in actions.iter_mut() {
+        let Some(object) = action.as_object() else {
+            continue;
+        };
+        let mut flattened = if !object.contains_key("action") && object.len() == 1 {
+            let (kind, params) = object.iter().next().expect("single entry");
+            let mut flattened = params.as_object().cloned().unwrap_or_else(Map::new);
+            flattened.insert("action".into(), Value::String(kind.clone()));
+            flattened
+        } else {
+            object.clone()
+        };
+        flattened = flattened
+            .into_iter()
+            .map(|(key, value)| (camel_case(&key), value))
+            .collect();
+        *action = Value::Object(flattened);
+    }
+}
+
+fn camel_case(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Checks the plan against `policy`, sends it to Enso, optionally simulates the
+/// returned tx and appends the per_tx line. An Enso error becomes a `plan_failure`
+/// line and is returned as a run note.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_plan(
+    config: &RunnerConfig,
+    client: &EnsoClient,
+    writer: &mut ArtifactWriter,
+    plan: &LlmPlan,
+    policy: &PlanPolicy,
+    simulate: bool,
+    fork_url: Option<Url>,
+    label: &str,
+) -> RunnerResult<Option<String>> {
+    let fork_url = fork_url.or_else(|| config.default_fork_urls.get(&plan.chain_id()).cloned());
+    let violations = policy.check(plan);
+    record_violations(writer, &violations)?;
+
+    match plan {
+        LlmPlan::Route(request) => {
+            tracing::info!(chain_id = request.chain_id, "requesting /shortcuts/route");
+            let (response, step) = traced_enso(
+                writer,
+                None,
+                "/shortcuts/route",
+                request,
+                client.post_route(request),
+            )
+            .await?;
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => return record_enso_error(writer, plan, &err),
+            };
+            let execution = if simulate {
+                Some(traced_simulation(writer, step, request.chain_id, fork_url, &response.tx, label).await?)
+            } else {
+                None
+            };
+            writer
+                .append_route(request, &response, execution.as_ref(), &violations)
+                .map_err(RunnerError::Config)?;
+            Ok(None)
+        }
+        LlmPlan::Bundle(request) => {
+            let mut request = request.clone();
+            patch_bundle(&mut request, writer)?;
+            tracing::info!(chain_id = request.chain_id, "requesting /shortcuts/bundle");
+            let (response, step) = traced_enso(
+                writer,
+                None,
+                "/shortcuts/bundle",
+                &request,
+                client.post_bundle(&request),
+            )
+            .await?;
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => return record_enso_error(writer, plan, &err),
+            };
+            let execution = if simulate {
+                Some(traced_simulation(writer, step, request.chain_id, fork_url, &response.tx, label).await?)
+            } else {
+                None
+            };
+            writer
+                .append_bundle(&request, &response, execution.as_ref(), &violations)
+                .map_err(RunnerError::Config)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Appends a `plan_failure` line for a plan Enso refused and returns the run note.
+fn record_enso_error(
+    writer: &mut ArtifactWriter,
+    plan: &LlmPlan,
+    err: &RunnerError,
+) -> RunnerResult<Option<String>> {
+    tracing::warn!(error = %err, chain_id = plan.chain_id(), "Enso rejected the plan");
+    writer
+        .append_plan_failure(&plan.to_value()?, &err.to_string())
+        .map_err(RunnerError::Config)?;
+    Ok(Some(format!("enso_error: {err}")))
+}
+
+/// Sends one Enso request, recording an `enso_request` step and an `enso_response` step
+/// under it. The call's own error is handed back so tool agents can show it to the model;
+/// the response step index lets a simulation hang off it.
+pub(crate) async fn traced_enso<T: Serialize>(
+    writer: &mut ArtifactWriter,
+    parent: Option<usize>,
+    endpoint: &str,
+    body: &impl Serialize,
+    call: impl Future<Output = RunnerResult<T>>,
+) -> RunnerResult<(RunnerResult<T>, usize)> {
+    let request_step = record_child(
+        writer,
+        parent,
+        None,
+        StepKind::EnsoRequest {
+            endpoint: endpoint.into(),
+            body: serde_json::to_value(body)?,
+        },
+    )?;
+    let started = Instant::now();
+    let result = call.await;
+    let (body, error) = match &result {
+        Ok(response) => (serde_json::to_value(response)?, None),
+        Err(err) => (Value::Null, Some(err.to_string())),
+    };
+    let response_step = record_child(
+        writer,
+        Some(request_step),
+        Some(started.elapsed()),
+        StepKind::EnsoResponse {
+            endpoint: endpoint.into(),
+            body,
+            error,
+        },
+    )?;
+    Ok((result, response_step))
+}
+
+/// [`simulate_tx`] plus a `simulation_result` step under the Enso response that produced the tx.
+pub(crate) async fn traced_simulation(
+    writer: &mut ArtifactWriter,
+    parent: usize,
+    chain_id: u64,
+    fork_url: Option<Url>,
+    envelope: &TransactionEnvelope,
+    label: &str,
+) -> RunnerResult<ExecutionRecord> {
+    let started = Instant::now();
+    let execution = simulate_tx(chain_id, fork_url, envelope, label).await;
+    record_child(
+        writer,
+        Some(parent),
+        Some(started.elapsed()),
+        StepKind::SimulationResult {
+            status: execution.status,
+            gas_used: execution.gas_used,
+            transaction_hash: execution.transaction_hash.clone(),
+        },
+    )?;
+    Ok(execution)
+}
+
+/// The final completion of a planning session and how many repair rounds it took.
+#[derive(Debug)]
+pub struct PlanOutcome {
+    pub completion: Value,
+    pub plan: Result<LlmPlan, PlanError>,
+    pub repairs: u32,
+}
+
+/// Requests a plan and, while it fails validation, re-prompts the model with the
+/// errors up to `max_repairs` times. Every attempt is written to the trajectory.
+pub async fn request_plan(
+    provider: &dyn LlmProvider,
+    system_prompt: &str,
+    prompt: &str,
+    max_repairs: u32,
+    writer: &mut ArtifactWriter,
+) -> RunnerResult<PlanOutcome> {
+    let mut request = ChatRequest {
+        messages: vec![
+            json!({ "role": "system", "content": system_prompt }),
+            json!({ "role": "user", "content": prompt }),
+        ],
+        json_mode: true,
+        ..Default::default()
+    };
+    record_step(
+        writer,
+        StepKind::SystemPrompt {
+            text: system_prompt.into(),
+        },
+    )?;
+    let mut prompt_step = record_step(writer, StepKind::UserPrompt { text: prompt.into() })?;
+    let mut repairs = 0;
+
+    loop {
+        let (completion, step) = chat(provider, &request, writer, Some(prompt_step)).await?;
+        let plan = parse_plan(&completion);
+        record_validation(writer, step, &plan, repairs)?;
+
+        let err = match plan {
+            Err(err) if repairs < max_repairs => err,
+            plan => {
+                return Ok(PlanOutcome {
+                    completion,
+                    plan,
+                    repairs,
+                })
+            }
+        };
+
+        tracing::info!(attempt = repairs, error = %err, "re-prompting LLM with validation errors");
+        let previous = completion
+            .pointer("/choices/0/message/content")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| completion.to_string());
+        let repair = repair_prompt(&err);
+        prompt_step = record_child(
+            writer,
+            Some(step),
+            None,
+            StepKind::UserPrompt { text: repair.clone() },
+        )?;
+        request
+            .messages
+            .push(json!({ "role": "assistant", "content": previous }));
+        request
+            .messages
+            .push(json!({ "role": "user", "content": repair }));
+        repairs += 1;
+    }
+}
+
+/// Sends a chat request, adds its token usage and latency to the run totals and records
+/// the reply as a `model_message` step under `parent`. Returns the completion and the step index.
+pub async fn chat(
+    provider: &dyn LlmProvider,
+    request: &ChatRequest,
+    writer: &mut ArtifactWriter,
+    parent: Option<usize>,
+) -> RunnerResult<(Value, usize)> {
+    let started = Instant::now();
+    let completion = provider.chat(request).await?;
+    let elapsed = started.elapsed();
+    writer.record_llm_call(provider.name(), provider.model(), &completion, elapsed);
+    let step = record_child(
+        writer,
+        parent,
+        Some(elapsed),
+        StepKind::ModelMessage {
+            message: completion
+                .pointer("/choices/0/message")
+                .cloned()
+                .unwrap_or_else(|| completion.clone()),
+            usage: TokenUsage::from_completion(&completion),
+        },
+    )?;
+    Ok((completion, step))
+}
+
+/// Wraps a locally produced plan (offline stub) in the same outcome shape.
+pub fn offline_plan(plan: Value, writer: &mut ArtifactWriter) -> RunnerResult<PlanOutcome> {
+    let parsed = parse_plan(&plan);
+    let step = record_step(writer, StepKind::Plan { plan: plan.clone() })?;
+    record_validation(writer, step, &parsed, 0)?;
+    Ok(PlanOutcome {
+        completion: plan,
+        plan: parsed,
+        repairs: 0,
+    })
+}
+
+/// Records a provider call that never produced a plan as a `plan_failure` per_tx line,
+/// so nothing is executed on the model's behalf. Returns the run note.
+pub fn record_llm_error(
+    writer: &mut ArtifactWriter,
+    provider: &str,
+    err: &RunnerError,
+) -> RunnerResult<String> {
+    tracing::warn!(error = %err, provider, "LLM call failed; recording a plan failure");
+    writer
+        .append_plan_failure(
+            &json!({ "provider": provider }),
+            &format!("llm call failed: {err}"),
+        )
+        .map_err(RunnerError::Config)?;
+    Ok(format!("llm_error: {err}"))
+}
+
+/// Records a `plan_validation` event under the step that produced a rejected plan.
+fn record_validation(
+    writer: &mut ArtifactWriter,
+    parent: usize,
+    plan: &Result<LlmPlan, PlanError>,
+    attempt: u32,
+) -> RunnerResult<()> {
+    if let Err(err) = plan {
+        let errors = match err {
+            PlanError::Schema(errors) => errors.clone(),
+            other => vec![other.to_string()],
+        };
+        record_child(
+            writer,
+            Some(parent),
+            None,
+            StepKind::event("plan_validation", json!({ "attempt": attempt, "errors": errors })),
+        )?;
+    }
+    Ok(())
+}
+
+fn repair_prompt(err: &PlanError) -> String {
+    let errors = match err {
+        PlanError::Schema(errors) => errors
+            .iter()
+            .map(|e| format!("- {e}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => format!("- {other}"),
+    };
+    format!(
+        "Your previous plan was rejected:\n{errors}\nReturn a corrected plan as strict JSON only, \
+         with exactly one of `route` or `bundle`."
+    )
+}
+
+/// Executes a planning outcome. Malformed plans and plans Enso rejects become a
+/// `plan_failure` per_tx line and the error is returned as a run note.
+pub async fn execute_outcome(
+    config: &RunnerConfig,
+    writer: &mut ArtifactWriter,
+    outcome: &PlanOutcome,
+    policy: &PlanPolicy,
+    simulate: bool,
+    fork_url: Option<Url>,
+    label: &str,
+) -> RunnerResult<Option<String>> {
+    let plan = match &outcome.plan {
+        Ok(plan) => plan,
+        Err(err) => {
+            tracing::warn!(error = %err, "LLM returned a malformed plan");
+            writer
+                .append_plan_failure(&outcome.completion, &err.to_string())
+                .map_err(RunnerError::Config)?;
+            return Ok(Some(format!("plan_failure: {err}")));
+        }
+    };
+
+    let client = EnsoClient::from_config(config).map_err(RunnerError::Config)?;
+    execute_plan(config, &client, writer, plan, policy, simulate, fork_url, label).await
+}
+
+/// Runs the envelope on an Anvil fork; executor failures are recorded as skipped.
+pub async fn simulate_tx(
+    chain_id: u64,
+    fork_url: Option<Url>,
+    envelope: &TransactionEnvelope,
+    label: &str,
+) -> ExecutionRecord {
+    let executor = AnvilExecutor::new(AnvilExecutorConfig { chain_id, fork_url });
+
+    match executor.execute(envelope, label).await {
+        Ok(record) => record,
+        Err(err) => {
+            tracing::warn!(error = %err, "Anvil execution failed; marking as skipped");
+            ExecutionRecord {
+                label: label.into(),
+                envelope: envelope.clone(),
+                status: ExecutionStatus::Skipped,
+                gas_used: None,
+                transaction_hash: None,
+                logs: Vec::new(),
+                traces: Vec::new(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enso_client::ActionRequest;
+    use serde_json::json;
+
+    #[test]
+    fn parses_route_from_fenced_completion() {
+        let completion = json!({
+            "choices": [{ "message": { "content": "```json\n{\"route\":{\"chainId\":1,\"tokenIn\":{\"symbol\":\"USDC\"},\"tokenOut\":{\"symbol\":\"WETH\"},\"amount\":\"1\"}}\n```" } }]
+        });
+        let plan = parse_plan(&completion).expect("route plan");
+        assert!(matches!(plan, LlmPlan::Route(ref r) if r.chain_id == 1 && r.amount == "1"));
+    }
+
+    #[test]
+    fn parses_bundle_action_shorthand() {
+        let plan = parse_plan(&json!({
+            "bundle": {
+                "chainId": 1,
+                "actions": [
+                    { "approve": { "token": "0xa", "spender": "router" } },
+                    { "swap": { "tokenIn": "0xa", "tokenOut": "0xb", "amount": "5", "slippageBps": 30 } }
+                ]
+            }
+        }))
+        .expect("bundle plan");
+        let LlmPlan::Bundle(bundle) = plan else {
+            panic!("expected bundle");
+        };
+        assert!(matches!(bundle.actions[1], ActionRequest::Swap { ref token_out, .. } if token_out == "0xb"));
+    }
+
+    #[test]
+    fn rejects_unknown_shape() {
+        let err = parse_plan(&json!({ "transaction": { "steps": [] } })).unwrap_err();
+        assert!(matches!(err, PlanError::UnknownShape));
+    }
+}
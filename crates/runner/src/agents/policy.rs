@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    artifacts::ArtifactWriter,
+    enso_client::{ActionRequest, TokenRef},
+    error::RunnerResult,
+    trajectory::StepKind,
+};
+
+use super::{approvals::NATIVE_TOKEN, plan::LlmPlan, tools::record_step};
+
+/// SPEC §8: at most five steps per plan.
+pub const MAX_PLAN_ACTIONS: usize = 5;
+/// Slippage outside this range is either unexecutable (0) or an obvious value leak.
+pub const MIN_SLIPPAGE_BPS: u16 = 1;
+pub const MAX_SLIPPAGE_BPS: u16 = 500;
+
+static ADDRESS: Lazy<Regex> = Lazy::new(|| Regex::new(r"0x[0-9a-fA-F]{40}").expect("valid regex"));
+
+/// Which SPEC §8 hard rule a plan broke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    TooManyActions,
+    LendingOutsideBundle,
+    SlippageOutOfBounds,
+    UnknownAddress,
+}
+
+/// One broken rule, recorded on the per_tx line so the evaluator can penalize it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    pub rule: PolicyRule,
+    pub message: String,
+    /// Bundle action the violation refers to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_index: Option<usize>,
+}
+
+/// Hard rules for LLM plans. Violations do not block execution; they are recorded.
+#[derive(Debug, Clone)]
+pub struct PlanPolicy {
+    pub max_actions: usize,
+    pub min_slippage_bps: u16,
+    pub max_slippage_bps: u16,
+    /// Lower-cased token addresses the agent was given; `None` skips the address check.
+    pub known_addresses: Option<HashSet<String>>,
+}
+
+impl Default for PlanPolicy {
+    fn default() -> Self {
+        Self {
+            max_actions: MAX_PLAN_ACTIONS,
+            min_slippage_bps: MIN_SLIPPAGE_BPS,
+            max_slippage_bps: MAX_SLIPPAGE_BPS,
+            known_addresses: None,
+        }
+    }
+}
+
+impl PlanPolicy {
+    /// Default policy whose token map is every address quoted in the prompt.
+    pub fn from_prompt(prompt: &str) -> Self {
+        let mut policy = Self::default();
+        policy.allow_addresses(ADDRESS.find_iter(prompt).map(|m| m.as_str()));
+        policy
+    }
+
+    /// Adds addresses to the token map, e.g. from `/tokens` or wallet balance results.
+    pub fn allow_addresses<'a>(&mut self, addresses: impl IntoIterator<Item = &'a str>) {
+        let mut addresses = addresses.into_iter().peekable();
+        if addresses.peek().is_none() {
+            return;
+        }
+        self.known_addresses
+            .get_or_insert_with(HashSet::new)
+            .extend(addresses.map(str::to_ascii_lowercase));
+    }
+
+    pub fn check(&self, plan: &LlmPlan) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        match plan {
+            LlmPlan::Route(request) => {
+                if let Some(action) = request.extra.get("action").and_then(|v| v.as_str()) {
+                    if is_lending(action) {
+                        violations.push(violation(
+                            PolicyRule::LendingOutsideBundle,
+                            format!("`{action}` must be part of a bundle, not a route"),
+                            None,
+                        ));
+                    }
+                }
+                self.check_slippage(request.slippage_bps, None, &mut violations);
+                for token in [&request.token_in, &request.token_out] {
+                    self.check_token_ref(token, &mut violations);
+                }
+            }
+            LlmPlan::Bundle(request) => {
+                if request.actions.len() > self.max_actions {
+                    violations.push(violation(
+                        PolicyRule::TooManyActions,
+                        format!(
+                            "bundle has {} actions; at most {} are allowed",
+                            request.actions.len(),
+                            self.max_actions
+                        ),
+                        None,
+                    ));
+                }
+                for (index, action) in request.actions.iter().enumerate() {
+                    if let ActionRequest::Swap { slippage_bps, .. } = action {
+                        self.check_slippage(*slippage_bps, Some(index), &mut violations);
+                    }
+                    for address in action_addresses(action) {
+                        self.check_address(address, Some(index), &mut violations);
+                    }
+                }
+            }
+        }
+        violations
+    }
+
+    fn check_slippage(&self, bps: Option<u16>, index: Option<usize>, violations: &mut Vec<PolicyViolation>) {
+        let Some(bps) = bps else {
+            return;
+        };
+        if bps < self.min_slippage_bps || bps > self.max_slippage_bps {
+            violations.push(violation(
+                PolicyRule::SlippageOutOfBounds,
+                format!(
+                    "slippage {bps} bps is outside {}..={} bps",
+                    self.min_slippage_bps, self.max_slippage_bps
+                ),
+                index,
+            ));
+        }
+    }
+
+    fn check_token_ref(&self, token: &TokenRef, violations: &mut Vec<PolicyViolation>) {
+        if let Some(address) = &token.address {
+            self.check_address(address, None, violations);
+        }
+    }
+
+    fn check_address(&self, address: &str, index: Option<usize>, violations: &mut Vec<PolicyViolation>) {
+        let Some(known) = &self.known_addresses else {
+            return;
+        };
+        let key = address.to_ascii_lowercase();
+        // Only literal addresses are checked; symbols and `router`-style aliases resolve server-side.
+        if !ADDRESS.is_match(&key) || key == NATIVE_TOKEN || known.contains(&key) {
+            return;
+        }
+        violations.push(violation(
+            PolicyRule::UnknownAddress,
+            format!("{address} is not in the provided token map"),
+            index,
+        ));
+    }
+}
+
+/// Writes a `policy_violation` trajectory step when a plan broke any rule.
+pub fn record_violations(writer: &mut ArtifactWriter, violations: &[PolicyViolation]) -> RunnerResult<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    tracing::warn!(count = violations.len(), "plan violates policy");
+    record_step(
+        writer,
+        StepKind::PolicyViolation {
+            violations: violations.to_vec(),
+        },
+    )?;
+    Ok(())
+}
+
+fn violation(rule: PolicyRule, message: String, action_index: Option<usize>) -> PolicyViolation {
+    PolicyViolation {
+        rule,
+        message,
+        action_index,
+    }
+}
+
+fn is_lending(action: &str) -> bool {
+    matches!(action, "deposit" | "borrow" | "repay")
+}
+
+fn action_addresses(action: &ActionRequest) -> Vec<&str> {
+    match action {
+        ActionRequest::Approve { token, .. } => vec![token],
+        ActionRequest::Swap {
+            token_in, token_out, ..
+        } => vec![token_in, token_out],
+        ActionRequest::Deposit { asset, .. }
+        | ActionRequest::Borrow { asset, .. }
+        | ActionRequest::Repay { asset, .. }
+        | ActionRequest::Stake { asset, .. } => vec![asset],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::plan::parse_plan;
+    use serde_json::json;
+
+    const USDC: &str = "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+    fn rules(violations: &[PolicyViolation]) -> Vec<PolicyRule> {
+        violations.iter().map(|v| v.rule).collect()
+    }
+
+    #[test]
+    fn flags_action_count_slippage_and_unknown_addresses() {
+        let policy = PlanPolicy::from_prompt(&format!("Swap USDC ({USDC}) into WETH ({WETH})."));
+        let swap = json!({ "swap": { "tokenIn": USDC, "tokenOut": WETH, "amount": "1", "slippageBps": 30 } });
+        let mut actions = vec![swap.clone(); 5];
+        actions.push(
+            json!({ "swap": { "tokenIn": USDC, "tokenOut": "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+                                       "amount": "1", "slippageBps": 2000 } }),
+        );
+        let plan = parse_plan(&json!({ "bundle": { "chainId": 1, "actions": actions } })).unwrap();
+
+        let violations = policy.check(&plan);
+        assert_eq!(
+            rules(&violations),
+            [
+                PolicyRule::TooManyActions,
+                PolicyRule::SlippageOutOfBounds,
+                PolicyRule::UnknownAddress
+            ]
+        );
+        assert_eq!(violations[2].action_index, Some(5));
+
+        let ok = parse_plan(&json!({ "bundle": { "chainId": 1, "actions": [swap] } })).unwrap();
+        assert!(policy.check(&ok).is_empty());
+    }
+
+    #[test]
+    fn flags_lending_routes_and_skips_addresses_without_a_token_map() {
+        let plan = parse_plan(&json!({
+            "route": { "chainId": 1, "tokenIn": { "address": USDC }, "tokenOut": { "symbol": "aWETH" },
+                       "amount": "1", "action": "deposit" }
+        }))
+        .unwrap();
+        assert_eq!(
+            rules(&PlanPolicy::default().check(&plan)),
+            [PolicyRule::LendingOutsideBundle]
+        );
+    }
+}
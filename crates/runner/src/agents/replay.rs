@@ -0,0 +1,200 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use clap::Args as ClapArgs;
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::{
+    artifacts::RunContext,
+    config::RunnerConfig,
+    enso_client::EnsoClient,
+    error::{RunnerError, RunnerResult},
+    redact::Redactor,
+    trajectory::{read_trajectory, StepKind},
+};
+
+use super::{
+    plan::{execute_plan, parse_plan, LlmPlan},
+    policy::PlanPolicy,
+    tools::{record_step, ToolCall, ToolKit},
+};
+
+/// Tools whose calls change what ends up in per_tx.jsonl; lookups are not replayed.
+const REPLAYED_TOOLS: [&str; 3] = ["post_route", "post_bundle", "simulate"];
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct Args {
+    /// A run directory, its trajectory.jsonl, or a JSON/YAML plan file (one plan or a list)
+    #[arg(long)]
+    pub from: PathBuf,
+    /// Simulate replayed plans on an Anvil fork (tool-call `simulate` steps always run)
+    #[arg(long)]
+    pub simulate: bool,
+    /// Override fork URL; defaults to config per chain-id
+    #[arg(long)]
+    pub fork_url: Option<Url>,
+    /// Artifact label
+    #[arg(long, default_value = "replay")]
+    pub label: String,
+}
+
+/// One recorded decision to re-issue.
+#[derive(Debug, Clone)]
+pub enum ReplayStep {
+    Plan(LlmPlan),
+    ToolCall(ToolCall),
+}
+
+pub async fn run(config: &RunnerConfig, args: Args) -> RunnerResult<()> {
+    let steps = load_steps(&args.from)?;
+    tracing::info!(steps = steps.len(), source = %args.from.display(), "replaying recorded plans");
+
+    let run_context =
+        RunContext::create(config.artifacts_dir.clone(), &args.label).map_err(RunnerError::Config)?;
+    let mut writer = run_context
+        .writer(Redactor::from_config(config))
+        .map_err(RunnerError::Config)?;
+    record_step(
+        &mut writer,
+        StepKind::event(
+            "replay",
+            json!({ "source": args.from.display().to_string(), "steps": steps.len() }),
+        ),
+    )?;
+
+    let client = EnsoClient::from_config(config).map_err(RunnerError::Config)?;
+    let mut toolkit = ToolKit::new(config, args.fork_url.clone(), &args.label)?;
+
+    for step in &steps {
+        match step {
+            ReplayStep::Plan(plan) => {
+                // Recorded as a planner step so a replay can itself be replayed.
+                record_step(
+                    &mut writer,
+                    StepKind::Plan {
+                        plan: plan.to_value()?,
+                    },
+                )?;
+                execute_plan(
+                    config,
+                    &client,
+                    &mut writer,
+                    plan,
+                    &PlanPolicy::default(),
+                    args.simulate,
+                    args.fork_url.clone(),
+                    &args.label,
+                )
+                .await?;
+            }
+            ReplayStep::ToolCall(call) => {
+                toolkit.dispatch(&mut writer, call, None).await?;
+            }
+        }
+    }
+
+    let notes = format!("replayed {} steps from {}", steps.len(), args.from.display());
+    writer
+        .finalize("replay", Some(notes))
+        .map_err(RunnerError::Config)?;
+
+    Ok(())
+}
+
+/// Loads replay steps from a run directory, a trajectory.jsonl or a plan file.
+pub fn load_steps(path: &Path) -> RunnerResult<Vec<ReplayStep>> {
+    let path = if path.is_dir() {
+        path.join("trajectory.jsonl")
+    } else {
+        path.to_path_buf()
+    };
+    let data = fs::read_to_string(&path)?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("jsonl") => steps_from_trajectory(&data),
+        Some("yaml" | "yml") => steps_from_plan_file(serde_yaml::from_str(&data)?),
+        _ => steps_from_plan_file(serde_json::from_str(&data)?),
+    }
+}
+
+/// Extracts the plans that were executed in a recorded run: model messages and plan
+/// steps that parse into a valid plan (rejected repair attempts are skipped) and the
+/// quote/simulate tool calls of a tool-calling agent. Old-format trajectories are accepted.
+pub fn steps_from_trajectory(data: &str) -> RunnerResult<Vec<ReplayStep>> {
+    let mut steps = Vec::new();
+    for step in read_trajectory(data).map_err(RunnerError::Config)? {
+        let plan = match step.kind {
+            // Planner replies carry the plan as message content, like a completion's first choice.
+            StepKind::ModelMessage { message, .. } => {
+                parse_plan(&json!({ "choices": [{ "message": message }] }))
+            }
+            StepKind::Plan { plan } => parse_plan(&plan),
+            StepKind::ToolCall { id, name, arguments } => {
+                if REPLAYED_TOOLS.contains(&name.as_str()) {
+                    steps.push(ReplayStep::ToolCall(ToolCall { id, name, arguments }));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let Ok(plan) = plan {
+            steps.push(ReplayStep::Plan(plan));
+        }
+    }
+    Ok(steps)
+}
+
+fn steps_from_plan_file(value: Value) -> RunnerResult<Vec<ReplayStep>> {
+    let plans = match value {
+        Value::Array(plans) => plans,
+        plan => vec![plan],
+    };
+    plans
+        .iter()
+        .enumerate()
+        .map(|(idx, plan)| {
+            parse_plan(plan)
+                .map(ReplayStep::Plan)
+                .map_err(|err| RunnerError::Config(anyhow!("plan #{idx} is invalid: {err}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_executed_plans_and_quote_tool_calls() {
+        let trajectory = [
+            json!({ "timestamp": "2024-01-01T00:00:00Z", "role": "planner",
+                    "content": { "route": { "chainId": 1 } } }),
+            json!({ "timestamp": "2024-01-01T00:00:01Z", "role": "plan_validation",
+                    "content": { "attempt": 0, "errors": ["missing tokenIn"] } }),
+            json!({ "timestamp": "2024-01-01T00:00:02Z", "role": "planner",
+                    "content": { "route": { "chainId": 1, "tokenIn": { "symbol": "USDC" },
+                                            "tokenOut": { "symbol": "WETH" }, "amount": "1" } } }),
+            json!({ "timestamp": "2024-01-01T00:00:03Z", "role": "tool_call",
+                    "content": { "id": "c1", "name": "get_tokens", "arguments": {} } }),
+            json!({ "timestamp": "2024-01-01T00:00:04Z", "role": "tool_call",
+                    "content": { "id": "c2", "name": "simulate", "arguments": { "quoteId": 0 } } }),
+            json!({ "index": 5, "timestamp": "2024-01-01T00:00:05Z", "type": "model_message",
+                    "message": { "role": "assistant", "content": "{\"route\": {\"chainId\": 10, \
+                      \"tokenIn\": {\"symbol\": \"USDC\"}, \"tokenOut\": {\"symbol\": \"OP\"}, \"amount\": \"2\"}}" } }),
+        ]
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        let steps = steps_from_trajectory(&trajectory).unwrap();
+        assert_eq!(steps.len(), 3);
+        assert!(matches!(&steps[0], ReplayStep::Plan(LlmPlan::Route(r)) if r.amount == "1"));
+        assert!(matches!(&steps[1], ReplayStep::ToolCall(call) if call.name == "simulate"));
+        assert!(matches!(&steps[2], ReplayStep::Plan(LlmPlan::Route(r)) if r.chain_id == 10));
+    }
+}
@@ -0,0 +1,273 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{json, Value};
+
+/// JSON Schema for the strict-JSON plan vocabulary in SPEC §8. Bundle actions are
+/// validated after the `{ "swap": {...} }` shorthand has been flattened.
+pub static PLAN_SCHEMA: Lazy<Value> = Lazy::new(|| {
+    let token = json!({
+        "type": "object",
+        "properties": {
+            "address": { "type": "string", "pattern": "^0x[0-9a-fA-F]{40}$" },
+            "symbol": { "type": "string" }
+        },
+        "anyOf": [{ "required": ["address"] }, { "required": ["symbol"] }]
+    });
+    let amount = json!({ "type": "string", "pattern": "^[0-9]+$" });
+    let slippage = json!({ "type": "integer", "minimum": 0, "maximum": 10000 });
+    let action_fields = |kind: &str, required: Value| {
+        json!({
+            "if": { "properties": { "action": { "const": kind } } },
+            "then": { "required": required }
+        })
+    };
+
+    json!({
+        "type": "object",
+        "oneOf": [{ "required": ["route"] }, { "required": ["bundle"] }],
+        "properties": {
+            "route": {
+                "type": "object",
+                "required": ["chainId", "tokenIn", "tokenOut", "amount"],
+                "properties": {
+                    "chainId": { "type": "integer", "minimum": 1 },
+                    "tokenIn": token,
+                    "tokenOut": token,
+                    "amount": amount,
+                    "slippageBps": slippage,
+                    "recipient": { "type": "string" }
+                }
+            },
+            "bundle": {
+                "type": "object",
+                "required": ["chainId", "actions"],
+                "properties": {
+                    "chainId": { "type": "integer", "minimum": 1 },
+                    "recipient": { "type": "string" },
+                    "routingStrategy": { "enum": ["router", "delegate"] },
+                    "actions": {
+                        "type": "array",
+                        "minItems": 1,
+                        "items": {
+                            "type": "object",
+                            "required": ["action"],
+                            "properties": {
+                                "action": {
+                                    "enum": ["approve", "swap", "deposit", "borrow", "repay", "stake", "harvest", "bridge"]
+                                },
+                                "amount": amount,
+                                "slippageBps": slippage,
+                                "destinationChain": { "type": "integer", "minimum": 1 }
+                            },
+                            "allOf": [
+                                action_fields("approve", json!(["token", "spender"])),
+                                action_fields("swap", json!(["tokenIn", "tokenOut", "amount"])),
+                                action_fields("deposit", json!(["protocol", "asset"])),
+                                action_fields("borrow", json!(["protocol", "asset", "amount"])),
+                                action_fields("repay", json!(["protocol", "asset", "amount"])),
+                                action_fields("stake", json!(["protocol", "asset", "amount"])),
+                                action_fields("harvest", json!(["protocol"])),
+                                action_fields("bridge", json!(["protocol", "amount", "destinationChain", "recipient"]))
+                            ]
+                        }
+                    }
+                }
+            }
+        }
+    })
+});
+
+/// Validates a plan against [`PLAN_SCHEMA`], returning one message per violation.
+pub fn validate_plan(plan: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate(&PLAN_SCHEMA, plan, "", &mut errors);
+    errors
+}
+
+/// Minimal JSON Schema interpreter covering the keywords used by [`PLAN_SCHEMA`].
+fn validate(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "/" } else { path };
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !type_matches(expected, value) {
+            errors.push(format!("{at}: expected {expected}, found {}", type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            errors.push(format!("{at}: expected {expected}"));
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{at}: {value} is not one of {}",
+                Value::Array(options.clone())
+            ));
+        }
+    }
+
+    if let (Some(pattern), Some(text)) = (schema.get("pattern").and_then(Value::as_str), value.as_str()) {
+        if !compiled(pattern).is_match(text) {
+            errors.push(format!("{at}: {text:?} does not match {pattern}"));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < min {
+                errors.push(format!("{at}: {number} is below minimum {min}"));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > max {
+                errors.push(format!("{at}: {number} is above maximum {max}"));
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    errors.push(format!("{at}: missing required field `{key}`"));
+                }
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (key, subschema) in properties {
+                if let Some(child) = object.get(key) {
+                    validate(subschema, child, &format!("{path}/{key}"), errors);
+                }
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                errors.push(format!("{at}: expected at least {min} items"));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if items.len() as u64 > max {
+                errors.push(format!("{at}: expected at most {max} items"));
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (idx, item) in items.iter().enumerate() {
+                validate(item_schema, item, &format!("{path}/{idx}"), errors);
+            }
+        }
+    }
+
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for subschema in all {
+            validate(subschema, value, path, errors);
+        }
+    }
+
+    if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+        if !any.iter().any(|subschema| is_valid(subschema, value)) {
+            errors.push(format!("{at}: does not satisfy any allowed shape"));
+        }
+    }
+
+    if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+        let matched = one.iter().filter(|subschema| is_valid(subschema, value)).count();
+        if matched != 1 {
+            errors.push(format!(
+                "{at}: expected exactly one allowed shape, matched {matched}"
+            ));
+        }
+    }
+
+    if let (Some(condition), Some(then)) = (schema.get("if"), schema.get("then")) {
+        if is_valid(condition, value) {
+            validate(then, value, path, errors);
+        }
+    }
+}
+
+fn is_valid(schema: &Value, value: &Value) -> bool {
+    let mut errors = Vec::new();
+    validate(schema, value, "", &mut errors);
+    errors.is_empty()
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn compiled(pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap_or_else(|_| Regex::new("^$").expect("empty regex"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_route_and_bundle() {
+        let route = json!({ "route": {
+            "chainId": 1,
+            "tokenIn": { "address": "0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" },
+            "tokenOut": { "symbol": "WETH" },
+            "amount": "100000000",
+            "slippageBps": 30
+        }});
+        assert!(validate_plan(&route).is_empty(), "{:?}", validate_plan(&route));
+
+        let bundle = json!({ "bundle": {
+            "chainId": 1,
+            "actions": [
+                { "action": "approve", "token": "0xa", "spender": "router" },
+                { "action": "deposit", "protocol": "aave_v3", "asset": "0xa" }
+            ]
+        }});
+        assert!(validate_plan(&bundle).is_empty(), "{:?}", validate_plan(&bundle));
+    }
+
+    #[test]
+    fn reports_missing_fields_with_paths() {
+        let plan = json!({ "bundle": {
+            "chainId": 1,
+            "actions": [{ "action": "swap", "tokenIn": "0xa", "amount": "1" }]
+        }});
+        let errors = validate_plan(&plan);
+        assert_eq!(
+            errors,
+            vec!["/bundle/actions/0: missing required field `tokenOut`".to_string()]
+        );
+    }
+
+    #[test]
+    fn rejects_plans_with_both_shapes() {
+        let plan = json!({ "route": {}, "bundle": {} });
+        let errors = validate_plan(&plan);
+        assert!(errors.iter().any(|e| e.contains("exactly one allowed shape")));
+    }
+}
@@ -0,0 +1,444 @@
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::{
+    artifacts::{ArtifactWriter, ExecutionRecord},
+    config::RunnerConfig,
+    enso_client::{
+        BundleRequest, BundleResponse, EnsoClient, RouteRequest, RouteResponse, WalletBalancesRequest,
+    },
+    error::{RunnerError, RunnerResult},
+    trajectory::StepKind,
+};
+
+use super::{
+    approvals::patch_bundle,
+    plan::{parse_plan, traced_enso, traced_simulation, LlmPlan},
+    policy::{record_violations, PlanPolicy, PolicyViolation},
+};
+
+/// Upper bound on tokens returned by `get_tokens`, to keep tool results inside the context window.
+const MAX_TOKEN_RESULTS: usize = 50;
+
+/// A quote returned by `post_route` / `post_bundle`, waiting to be simulated.
+#[derive(Debug, Clone)]
+pub enum Quote {
+    Route(RouteRequest, RouteResponse),
+    Bundle(BundleRequest, BundleResponse),
+}
+
+/// A tool invocation requested by the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+}
+
+/// Enso + fork tools exposed to tool-calling agents. Quotes are kept by id; a
+/// per_tx line is written when a quote is simulated.
+pub struct ToolKit {
+    config: RunnerConfig,
+    client: EnsoClient,
+    fork_url: Option<Url>,
+    label: String,
+    policy: PlanPolicy,
+    quotes: Vec<Quote>,
+    /// `enso_response` step of each quote, the parent of its simulation step.
+    quote_steps: Vec<usize>,
+    violations: Vec<Vec<PolicyViolation>>,
+    simulated: Vec<bool>,
+    /// Tool call being dispatched, the parent of the Enso steps it causes.
+    current_call: Option<usize>,
+}
+
+impl ToolKit {
+    pub fn new(config: &RunnerConfig, fork_url: Option<Url>, label: &str) -> RunnerResult<Self> {
+        let client = EnsoClient::from_config(config).map_err(RunnerError::Config)?;
+        Ok(Self {
+            config: config.clone(),
+            client,
+            fork_url,
+            label: label.to_string(),
+            policy: PlanPolicy::default(),
+            quotes: Vec::new(),
+            quote_steps: Vec::new(),
+            violations: Vec::new(),
+            simulated: Vec::new(),
+            current_call: None,
+        })
+    }
+
+    /// Checks quoted plans against `policy`. Addresses returned by `get_tokens` and
+    /// `get_wallet_balances` are added to its token map.
+    pub fn with_policy(mut self, policy: PlanPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// OpenAI-compatible `tools` definitions.
+    pub fn definitions() -> Vec<Value> {
+        let plan_fields = "Same fields as the strict-JSON plan vocabulary (camelCase).";
+        vec![
+            function(
+                "get_tokens",
+                "List tokens known to Enso, optionally filtered by chain and symbol.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "chainId": { "type": "integer" },
+                        "symbol": { "type": "string" }
+                    }
+                }),
+            ),
+            function(
+                "get_wallet_balances",
+                "Token balances held by a wallet.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "wallet": { "type": "string" },
+                        "chainId": { "type": "integer" }
+                    },
+                    "required": ["wallet"]
+                }),
+            ),
+            function(
+                "post_route",
+                &format!("Quote a single route via /shortcuts/route. {plan_fields} Returns a quoteId."),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "chainId": { "type": "integer" },
+                        "tokenIn": { "type": "object" },
+                        "tokenOut": { "type": "object" },
+                        "amount": { "type": "string" },
+                        "slippageBps": { "type": "integer" },
+                        "recipient": { "type": "string" }
+                    },
+                    "required": ["chainId", "tokenIn", "tokenOut", "amount"]
+                }),
+            ),
+            function(
+                "post_bundle",
+                &format!(
+                    "Quote a multi-action bundle via /shortcuts/bundle. {plan_fields} Returns a quoteId."
+                ),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "chainId": { "type": "integer" },
+                        "actions": { "type": "array", "items": { "type": "object" } },
+                        "recipient": { "type": "string" }
+                    },
+                    "required": ["chainId", "actions"]
+                }),
+            ),
+            function(
+                "simulate",
+                "Execute a quoted transaction on a fork and record it. Only simulated quotes count; each can be simulated once.",
+                json!({
+                    "type": "object",
+                    "properties": { "quoteId": { "type": "integer" } },
+                    "required": ["quoteId"]
+                }),
+            ),
+        ]
+    }
+
+    /// Runs a tool. Tool-level failures are returned to the model as `{ "error": ... }`;
+    /// only artifact write failures abort the run.
+    pub async fn call(
+        &mut self,
+        name: &str,
+        arguments: &Value,
+        writer: &mut ArtifactWriter,
+    ) -> RunnerResult<Value> {
+        let result = match name {
+            "get_tokens" => self.get_tokens(arguments).await,
+            "get_wallet_balances" => self.get_wallet_balances(arguments).await,
+            "post_route" => self.post_plan(json!({ "route": arguments }), writer).await?,
+            "post_bundle" => self.post_plan(json!({ "bundle": arguments }), writer).await?,
+            "simulate" => return self.simulate(arguments, writer).await,
+            other => Err(format!("unknown tool `{other}`")),
+        };
+        Ok(result.unwrap_or_else(|error| json!({ "error": error })))
+    }
+
+    /// Runs a tool call and writes both the call (under `parent`, usually the model message
+    /// that requested it) and its result to the trajectory.
+    pub async fn dispatch(
+        &mut self,
+        writer: &mut ArtifactWriter,
+        call: &ToolCall,
+        parent: Option<usize>,
+    ) -> RunnerResult<Value> {
+        let step = record_child(
+            writer,
+            parent,
+            None,
+            StepKind::ToolCall {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                arguments: call.arguments.clone(),
+            },
+        )?;
+        self.current_call = Some(step);
+        let started = Instant::now();
+        let result = self.call(&call.name, &call.arguments, writer).await;
+        self.current_call = None;
+        let result = result?;
+        record_child(
+            writer,
+            Some(step),
+            Some(started.elapsed()),
+            StepKind::ToolResult {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                result: result.clone(),
+            },
+        )?;
+        Ok(result)
+    }
+
+    /// Number of quotes that were never simulated (and therefore not recorded).
+    pub fn unsimulated(&self) -> usize {
+        self.simulated.iter().filter(|done| !**done).count()
+    }
+
+    async fn get_tokens(&mut self, arguments: &Value) -> Result<Value, String> {
+        let chain_id = arguments.get("chainId").and_then(Value::as_u64);
+        let symbol = arguments.get("symbol").and_then(Value::as_str);
+        let tokens = self.client.get_tokens().await.map_err(|err| err.to_string())?;
+        let matches: Vec<_> = tokens
+            .into_iter()
+            .filter(|token| chain_id.is_none_or(|id| token.chain_id == id))
+            .filter(|token| symbol.is_none_or(|s| token.symbol.eq_ignore_ascii_case(s)))
+            .take(MAX_TOKEN_RESULTS)
+            .collect();
+        self.policy
+            .allow_addresses(matches.iter().map(|token| token.address.as_str()));
+        serde_json::to_value(matches).map_err(|err| err.to_string())
+    }
+
+    async fn get_wallet_balances(&mut self, arguments: &Value) -> Result<Value, String> {
+        let wallet = arguments
+            .get("wallet")
+            .and_then(Value::as_str)
+            .ok_or("missing `wallet`")?;
+        let request = WalletBalancesRequest {
+            wallet: wallet.to_string(),
+            use_eoa: Some(true),
+            chain_id: arguments.get("chainId").and_then(Value::as_u64),
+        };
+        let balances = self
+            .client
+            .get_wallet_balances(&request)
+            .await
+            .map_err(|err| err.to_string())?;
+        self.policy.allow_addresses(
+            balances
+                .balances
+                .iter()
+                .map(|balance| balance.token.address.as_str()),
+        );
+        serde_json::to_value(balances).map_err(|err| err.to_string())
+    }
+
+    /// Quotes a plan. Bundles get their approves normalized first; the patch is returned
+    /// to the model alongside the quote.
+    async fn post_plan(
+        &mut self,
+        plan: Value,
+        writer: &mut ArtifactWriter,
+    ) -> RunnerResult<Result<Value, String>> {
+        let plan = match parse_plan(&plan) {
+            Ok(plan) => plan,
+            Err(err) => return Ok(Err(err.to_string())),
+        };
+        let violations = self.policy.check(&plan);
+        record_violations(writer, &violations)?;
+        let parent = self.current_call;
+        let (quote, patch, step) = match plan {
+            LlmPlan::Route(request) => {
+                let (response, step) = traced_enso(
+                    writer,
+                    parent,
+                    "/shortcuts/route",
+                    &request,
+                    self.client.post_route(&request),
+                )
+                .await?;
+                match response {
+                    Ok(response) => (Quote::Route(request, response), None, step),
+                    Err(err) => return Ok(Err(err.to_string())),
+                }
+            }
+            LlmPlan::Bundle(mut request) => {
+                let patch = patch_bundle(&mut request, writer)?;
+                let (response, step) = traced_enso(
+                    writer,
+                    parent,
+                    "/shortcuts/bundle",
+                    &request,
+                    self.client.post_bundle(&request),
+                )
+                .await?;
+                match response {
+                    Ok(response) => (Quote::Bundle(request, response), Some(patch), step),
+                    Err(err) => return Ok(Err(err.to_string())),
+                }
+            }
+        };
+        let mut summary = match &quote {
+            Quote::Route(_, response) => json!({ "tx": response.tx, "route": response.route }),
+            Quote::Bundle(_, response) => json!({ "tx": response.tx, "bundle": response.bundle }),
+        };
+        if let Some(patch) = patch.filter(|patch| !patch.is_empty()) {
+            summary["approvals"] = serde_json::to_value(patch)?;
+        }
+        if !violations.is_empty() {
+            summary["policyViolations"] = serde_json::to_value(&violations)?;
+        }
+        self.quotes.push(quote);
+        self.quote_steps.push(step);
+        self.violations.push(violations);
+        self.simulated.push(false);
+        Ok(Ok(json!({ "quoteId": self.quotes.len() - 1, "quote": summary })))
+    }
+
+    async fn simulate(&mut self, arguments: &Value, writer: &mut ArtifactWriter) -> RunnerResult<Value> {
+        let Some(id) = arguments
+            .get("quoteId")
+            .and_then(Value::as_u64)
+            .map(|id| id as usize)
+        else {
+            return Ok(json!({ "error": "missing `quoteId`" }));
+        };
+        // A second simulation would append a duplicate per_tx line for the same quote.
+        if self.simulated.get(id).copied().unwrap_or(false) {
+            return Ok(json!({ "error": format!("quoteId {id} already simulated") }));
+        }
+        let Some(quote) = self.quotes.get(id) else {
+            return Ok(json!({ "error": format!("unknown quoteId {id}") }));
+        };
+
+        let (chain_id, envelope) = match quote {
+            Quote::Route(request, response) => (request.chain_id, &response.tx),
+            Quote::Bundle(request, response) => (request.chain_id, &response.tx),
+        };
+        let fork_url = self
+            .fork_url
+            .clone()
+            .or_else(|| self.config.default_fork_urls.get(&chain_id).cloned());
+        let execution = traced_simulation(
+            writer,
+            self.quote_steps[id],
+            chain_id,
+            fork_url,
+            envelope,
+            &self.label,
+        )
+        .await?;
+
+        let violations = &self.violations[id];
+        match quote {
+            Quote::Route(request, response) => {
+                writer.append_route(request, response, Some(&execution), violations)
+            }
+            Quote::Bundle(request, response) => {
+                writer.append_bundle(request, response, Some(&execution), violations)
+            }
+        }
+        .map_err(RunnerError::Config)?;
+        self.simulated[id] = true;
+
+        Ok(execution_summary(&execution))
+    }
+}
+
+/// Appends a top-level trajectory step and returns its index.
+pub(crate) fn record_step(writer: &mut ArtifactWriter, kind: StepKind) -> RunnerResult<usize> {
+    record_child(writer, None, None, kind)
+}
+
+/// Appends a step caused by `parent`, with the wall time of the operation behind it.
+pub(crate) fn record_child(
+    writer: &mut ArtifactWriter,
+    parent: Option<usize>,
+    duration: Option<Duration>,
+    kind: StepKind,
+) -> RunnerResult<usize> {
+    writer
+        .append_step(kind, parent, duration)
+        .map_err(RunnerError::Config)
+}
+
+fn function(name: &str, description: &str, parameters: Value) -> Value {
+    json!({
+        "type": "function",
+        "function": { "name": name, "description": description, "parameters": parameters }
+    })
+}
+
+fn execution_summary(execution: &ExecutionRecord) -> Value {
+    json!({
+        "status": execution.status,
+        "gasUsed": execution.gas_used,
+        "transactionHash": execution.transaction_hash,
+        "logs": execution.logs.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_config, test_dir};
+
+    use crate::artifacts::RunContext;
+    use crate::redact::Redactor;
+
+    #[test]
+    fn definitions_cover_all_tools() {
+        let names: Vec<_> = ToolKit::definitions()
+            .iter()
+            .filter_map(|tool| {
+                tool.pointer("/function/name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "get_tokens",
+                "get_wallet_balances",
+                "post_route",
+                "post_bundle",
+                "simulate"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_to_simulate_a_quote_twice() {
+        let dir = test_dir("tools");
+        let config = test_config(dir.clone());
+        let ctx = RunContext::create(dir.clone(), "tools-test").unwrap();
+        let mut writer = ctx.writer(Redactor::new(Vec::new())).unwrap();
+        let mut toolkit = ToolKit::new(&config, None, "tools-test").unwrap();
+        toolkit.simulated.push(true);
+
+        let result = toolkit
+            .simulate(&json!({ "quoteId": 0 }), &mut writer)
+            .await
+            .unwrap();
+        drop(writer);
+        let per_tx = std::fs::read_to_string(ctx.root.join("per_tx.jsonl")).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        assert_eq!(result, json!({ "error": "quoteId 0 already simulated" }));
+        assert!(per_tx.is_empty());
+    }
+}
@@ -2,13 +2,21 @@ use std::{
     fs::{self, File},
     io::{BufWriter, Write},
     path::PathBuf,
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::enso_client::{BundleRequest, BundleResponse, RouteRequest, RouteResponse, TransactionEnvelope};
+use crate::{
+    agents::policy::PolicyViolation,
+    enso_client::{BundleRequest, BundleResponse, RouteRequest, RouteResponse, TransactionEnvelope},
+    experiment::ExperimentRef,
+    llm::LlmUsage,
+    redact::Redactor,
+    trajectory::{StepKind, TrajectoryStep},
+};
 
 #[derive(Debug, Clone)]
 pub struct RunContext {
@@ -30,8 +38,8 @@ impl RunContext {
         Ok(Self { root, started_at })
     }
 
-    pub fn writer(&self) -> Result<ArtifactWriter> {
-        ArtifactWriter::new(self)
+    pub fn writer(&self, redactor: Redactor) -> Result<ArtifactWriter> {
+        ArtifactWriter::new(self, redactor)
     }
 }
 
@@ -40,10 +48,17 @@ pub struct ArtifactWriter {
     trajectory: BufWriter<File>,
     meta_path: PathBuf,
     run_started_at: DateTime<Utc>,
+    redactor: Redactor,
+    plan_repairs: Option<u32>,
+    llm_usage: Option<LlmUsage>,
+    balance_snapshot: Option<String>,
+    next_step: usize,
+    /// Index of the next per_tx line, stamped on it as `intent_id`.
+    next_line: usize,
 }
 
 impl ArtifactWriter {
-    fn new(ctx: &RunContext) -> Result<Self> {
+    fn new(ctx: &RunContext, redactor: Redactor) -> Result<Self> {
         let per_tx_path = ctx.root.join("per_tx.jsonl");
         let per_tx = BufWriter::new(File::create(&per_tx_path)?);
 
@@ -57,6 +72,12 @@ impl ArtifactWriter {
             trajectory,
             meta_path,
             run_started_at: ctx.started_at,
+            redactor,
+            plan_repairs: None,
+            llm_usage: None,
+            balance_snapshot: None,
+            next_step: 0,
+            next_line: 0,
         })
     }
 
@@ -65,14 +86,18 @@ impl ArtifactWriter {
         request: &RouteRequest,
         response: &RouteResponse,
         execution: Option<&ExecutionRecord>,
+        policy_violations: &[PolicyViolation],
     ) -> Result<()> {
         let artifact = RunArtifact::Route {
+            intent_id: Some(self.next_intent_id()),
             timestamp: Utc::now(),
             request: request.clone(),
             response: response.clone(),
             execution: execution.cloned(),
+            policy_violations: policy_violations.to_vec(),
+            balance_snapshot: self.balance_snapshot.clone(),
         };
-        Self::write_jsonl(&mut self.per_tx, &artifact)
+        Self::write_jsonl(&mut self.per_tx, &self.redactor, &artifact)
     }
 
     pub fn append_bundle(
@@ -80,18 +105,77 @@ impl ArtifactWriter {
         request: &BundleRequest,
         response: &BundleResponse,
         execution: Option<&ExecutionRecord>,
+        policy_violations: &[PolicyViolation],
     ) -> Result<()> {
         let artifact = RunArtifact::Bundle {
+            intent_id: Some(self.next_intent_id()),
             timestamp: Utc::now(),
             request: request.clone(),
             response: response.clone(),
             execution: execution.cloned(),
+            policy_violations: policy_violations.to_vec(),
+            balance_snapshot: self.balance_snapshot.clone(),
+        };
+        Self::write_jsonl(&mut self.per_tx, &self.redactor, &artifact)
+    }
+
+    pub fn append_plan_failure(&mut self, raw: &serde_json::Value, error: &str) -> Result<()> {
+        let artifact = RunArtifact::PlanFailure {
+            intent_id: Some(self.next_intent_id()),
+            timestamp: Utc::now(),
+            raw: raw.clone(),
+            error: error.to_string(),
         };
-        Self::write_jsonl(&mut self.per_tx, &artifact)
+        Self::write_jsonl(&mut self.per_tx, &self.redactor, &artifact)
     }
 
-    pub fn append_trajectory_step(&mut self, step: &TrajectoryStep) -> Result<()> {
-        Self::write_jsonl(&mut self.trajectory, step)
+    /// `tx-<line>`: joins the per_tx line to its evaluator breakdown in eval_per_tx.jsonl.
+    fn next_intent_id(&mut self) -> String {
+        let id = format!("tx-{}", self.next_line);
+        self.next_line += 1;
+        id
+    }
+
+    /// Appends a trajectory step and returns its index, to be used as a later step's parent.
+    pub fn append_step(
+        &mut self,
+        kind: StepKind,
+        parent: Option<usize>,
+        duration: Option<Duration>,
+    ) -> Result<usize> {
+        let step = TrajectoryStep {
+            index: self.next_step,
+            parent,
+            timestamp: Utc::now(),
+            duration_ms: duration.map(|duration| duration.as_millis() as u64),
+            kind,
+        };
+        Self::write_jsonl(&mut self.trajectory, &self.redactor, &step)?;
+        self.next_step += 1;
+        Ok(step.index)
+    }
+
+    /// Records how many schema repair rounds the planner needed; written to meta.json.
+    pub fn set_plan_repairs(&mut self, repairs: u32) {
+        self.plan_repairs = Some(repairs);
+    }
+
+    /// Tags every later per_tx line with the wallet snapshot the agent was shown.
+    pub fn set_balance_snapshot(&mut self, id: &str) {
+        self.balance_snapshot = Some(id.to_string());
+    }
+
+    /// Adds one LLM call to the run's usage totals; written to meta.json.
+    pub fn record_llm_call(
+        &mut self,
+        provider: &str,
+        model: &str,
+        completion: &serde_json::Value,
+        latency: std::time::Duration,
+    ) {
+        self.llm_usage
+            .get_or_insert_with(|| LlmUsage::new(provider, model))
+            .record(completion, latency);
     }
 
     pub fn finalize(&mut self, scenario: &str, notes: Option<String>) -> Result<()> {
@@ -100,15 +184,23 @@ impl ArtifactWriter {
             finished_at: Utc::now(),
             scenario: scenario.to_string(),
             notes,
+            plan_repairs: self.plan_repairs,
+            llm_usage: self.llm_usage.clone(),
+            experiment: None,
         };
 
+        let mut value = serde_json::to_value(&metadata)?;
+        self.redactor.redact_value(&mut value);
+
         let file = File::create(&self.meta_path)?;
-        serde_json::to_writer_pretty(file, &metadata)?;
+        serde_json::to_writer_pretty(file, &value)?;
         Ok(())
     }
 
-    fn write_jsonl<T: Serialize>(writer: &mut BufWriter<File>, value: &T) -> Result<()> {
-        serde_json::to_writer(&mut *writer, value)?;
+    fn write_jsonl<T: Serialize>(writer: &mut BufWriter<File>, redactor: &Redactor, value: &T) -> Result<()> {
+        let mut value = serde_json::to_value(value)?;
+        redactor.redact_value(&mut value);
+        serde_json::to_writer(&mut *writer, &value)?;
         writer.write_all(b"\n")?;
         writer.flush()?;
         Ok(())
@@ -120,17 +212,41 @@ impl ArtifactWriter {
 pub enum RunArtifact {
     #[serde(rename = "route")]
     Route {
+        /// Line id in this run, matched by `intent_id` in eval_per_tx.jsonl.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        intent_id: Option<String>,
         timestamp: DateTime<Utc>,
         request: RouteRequest,
         response: RouteResponse,
         execution: Option<ExecutionRecord>,
+        /// SPEC §8 plan rules the request broke; penalized by the evaluator.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        policy_violations: Vec<PolicyViolation>,
+        /// Id of the `balance_snapshot` trajectory step injected into the prompt.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        balance_snapshot: Option<String>,
     },
     #[serde(rename = "bundle")]
     Bundle {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        intent_id: Option<String>,
         timestamp: DateTime<Utc>,
         request: BundleRequest,
         response: BundleResponse,
         execution: Option<ExecutionRecord>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        policy_violations: Vec<PolicyViolation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        balance_snapshot: Option<String>,
+    },
+    /// An LLM completion that could not be turned into a route/bundle request.
+    #[serde(rename = "plan_failure")]
+    PlanFailure {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        intent_id: Option<String>,
+        timestamp: DateTime<Utc>,
+        raw: serde_json::Value,
+        error: String,
     },
 }
 
@@ -152,7 +268,7 @@ pub struct TxTrace {
     pub detail: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecutionStatus {
     Success,
@@ -160,19 +276,46 @@ pub enum ExecutionStatus {
     Skipped,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TrajectoryStep {
-    pub timestamp: DateTime<Utc>,
-    pub role: String,
-    pub content: serde_json::Value,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunMetadata {
     pub started_at: DateTime<Utc>,
     pub finished_at: DateTime<Utc>,
     pub scenario: String,
     pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_repairs: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_usage: Option<LlmUsage>,
+    /// Set when the run is one repeat of a `--repeats` experiment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<ExperimentRef>,
 }
 
 impl RunMetadata {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamps_line_ids_on_every_per_tx_line() {
+        let base = crate::config::test_dir("artifacts");
+        let ctx = RunContext::create(base.clone(), "ids").unwrap();
+        let mut writer = ctx.writer(Redactor::new(Vec::new())).unwrap();
+        writer
+            .append_plan_failure(&serde_json::json!({}), "bad plan")
+            .unwrap();
+        writer
+            .append_plan_failure(&serde_json::json!({}), "bad plan")
+            .unwrap();
+        drop(writer);
+
+        let per_tx = fs::read_to_string(ctx.root.join("per_tx.jsonl")).unwrap();
+        let ids: Vec<String> = per_tx
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["intent_id"].to_string())
+            .collect();
+        fs::remove_dir_all(base).unwrap();
+        assert_eq!(ids, [r#""tx-0""#, r#""tx-1""#]);
+    }
+}
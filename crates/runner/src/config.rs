@@ -15,8 +15,55 @@ pub struct RunnerConfig {
     pub artifacts_dir: PathBuf,
     pub default_fork_urls: HashMap<u64, Url>,
     pub openrouter_api_key: Option<String>,
+    pub llm: LlmConfig,
 }
 
+/// Which chat-completions backend the LLM agents talk to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum LlmProviderKind {
+    #[default]
+    #[serde(rename = "openrouter")]
+    OpenRouter,
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+}
+
+impl LlmProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OpenRouter => "openrouter",
+            Self::OpenAiCompatible => "openai_compatible",
+        }
+    }
+}
+
+impl std::str::FromStr for LlmProviderKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "openrouter" => Ok(Self::OpenRouter),
+            "openai_compatible" | "openai-compatible" | "openai" => Ok(Self::OpenAiCompatible),
+            other => anyhow::bail!("unknown LLM provider `{other}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmConfig {
+    pub provider: LlmProviderKind,
+    /// Defaults to the OpenRouter endpoint; required for `openai_compatible`.
+    pub base_url: Option<Url>,
+    pub model: String,
+    /// Falls back to `OPENROUTER_API_KEY` for the OpenRouter provider.
+    pub api_key: Option<String>,
+    pub temperature: Option<f32>,
+    pub seed: Option<u64>,
+    pub max_tokens: Option<u32>,
+}
+
+pub const DEFAULT_LLM_MODEL: &str = "openai/gpt-4.1-mini";
+
 impl RunnerConfig {
     pub fn load(path: Option<&Path>) -> Result<Self> {
         let file_cfg = if let Some(path) = path {
@@ -59,12 +106,48 @@ impl RunnerConfig {
             .ok()
             .or(file_cfg.openrouter_api_key.clone());
 
+        let llm = Self::llm_config(file_cfg.llm.clone().unwrap_or_default())?;
+
         Ok(Self {
             enso_base_url,
             enso_api_key,
             artifacts_dir,
             default_fork_urls,
             openrouter_api_key,
+            llm,
+        })
+    }
+
+    fn llm_config(section: LlmSection) -> Result<LlmConfig> {
+        fn env_parse<T: std::str::FromStr>(key: &str) -> Result<Option<T>> {
+            match std::env::var(key) {
+                Ok(value) => value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| anyhow::anyhow!("invalid {key}: {value}")),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let base_url = std::env::var("LLM_BASE_URL").ok().or(section.base_url);
+        let base_url = base_url
+            .map(|url| Url::parse(&with_trailing_slash(&url)))
+            .transpose()
+            .context("Invalid LLM_BASE_URL")?;
+
+        Ok(LlmConfig {
+            provider: env_parse("LLM_PROVIDER")?
+                .or(section.provider)
+                .unwrap_or_default(),
+            base_url,
+            model: std::env::var("LLM_MODEL")
+                .ok()
+                .or(section.model)
+                .unwrap_or_else(|| DEFAULT_LLM_MODEL.to_string()),
+            api_key: std::env::var("LLM_API_KEY").ok().or(section.api_key),
+            temperature: env_parse("LLM_TEMPERATURE")?.or(section.temperature),
+            seed: env_parse("LLM_SEED")?.or(section.seed),
+            max_tokens: env_parse("LLM_MAX_TOKENS")?.or(section.max_tokens),
         })
     }
 
@@ -98,6 +181,8 @@ struct FileConfig {
     forks: Option<HashMap<u64, Url>>,
     #[serde(default)]
     openrouter_api_key: Option<String>,
+    #[serde(default)]
+    llm: Option<LlmSection>,
 }
 
 impl Default for FileConfig {
@@ -107,6 +192,7 @@ impl Default for FileConfig {
             artifacts_dir: Some(PathBuf::from("runs")),
             forks: Some(HashMap::new()),
             openrouter_api_key: None,
+            llm: None,
         }
     }
 }
@@ -127,3 +213,57 @@ impl Default for EnsoSection {
         }
     }
 }
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct LlmSection {
+    #[serde(default)]
+    provider: Option<LlmProviderKind>,
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    api_key: Option<String>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+}
+
+/// `Url::join` drops the last path segment unless it ends with `/`.
+fn with_trailing_slash(url: &str) -> String {
+    if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{url}/")
+    }
+}
+
+/// A config with an unreachable Enso endpoint and no keys, writing runs under `dir`.
+#[cfg(test)]
+pub(crate) fn test_config(dir: PathBuf) -> RunnerConfig {
+    RunnerConfig {
+        enso_base_url: Url::parse("http://127.0.0.1:9").unwrap(),
+        enso_api_key: "test".into(),
+        artifacts_dir: dir,
+        default_fork_urls: HashMap::new(),
+        openrouter_api_key: None,
+        llm: LlmConfig {
+            provider: LlmProviderKind::default(),
+            base_url: None,
+            model: "test-model".into(),
+            api_key: None,
+            temperature: None,
+            seed: None,
+            max_tokens: None,
+        },
+    }
+}
+
+/// A scratch directory under the system temp dir, unique to `name` and this process.
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ensobench-{name}-{}", std::process::id()))
+}
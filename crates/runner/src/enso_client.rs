@@ -188,16 +188,18 @@ pub enum ActionRequest {
         spender: String,
     },
     Swap {
+        #[serde(alias = "tokenIn")]
         token_in: String,
+        #[serde(alias = "tokenOut")]
         token_out: String,
         amount: String,
-        #[serde(default)]
+        #[serde(default, alias = "slippageBps")]
         slippage_bps: Option<u16>,
     },
     Deposit {
         protocol: String,
         asset: String,
-        #[serde(default)]
+        #[serde(default, alias = "amountSource")]
         amount_source: Option<String>,
     },
     Borrow {
@@ -221,6 +223,7 @@ pub enum ActionRequest {
     Bridge {
         protocol: String,
         amount: String,
+        #[serde(alias = "destinationChain")]
         destination_chain: u64,
         recipient: String,
     },
@@ -0,0 +1,258 @@
+use std::{
+    fs::{self, File},
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Args as ClapArgs;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    artifacts::RunMetadata,
+    config::RunnerConfig,
+    error::{RunnerError, RunnerResult},
+};
+
+pub const MANIFEST_FILE: &str = "experiment.json";
+
+/// Sampling controls shared by the LLM agents.
+#[derive(ClapArgs, Debug, Clone, Default)]
+pub struct SamplingArgs {
+    /// Run the agent N times and group the runs under one experiment id
+    #[arg(long, default_value_t = 1)]
+    pub repeats: u32,
+    /// Sampling seed (overrides LLM_SEED); repeat i uses seed + i
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Sampling temperature (overrides LLM_TEMPERATURE)
+    #[arg(long)]
+    pub temperature: Option<f32>,
+}
+
+/// Written to `<experiment>/experiment.json` and rewritten after every repeat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentManifest {
+    pub experiment_id: String,
+    pub agent: String,
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub provider: String,
+    pub model: String,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    pub base_seed: u64,
+    pub repeats: u32,
+    pub runs: Vec<ExperimentRun>,
+}
+
+/// One repeat; `run_dir` is relative to the manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentRun {
+    pub repeat: u32,
+    pub seed: u64,
+    #[serde(default)]
+    pub run_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Tags a run's meta.json with the experiment it belongs to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentRef {
+    pub experiment_id: String,
+    pub repeat: u32,
+    pub seed: u64,
+}
+
+/// Runs `run` once, or `--repeats` times with consecutive seeds (wrapping past `u64::MAX`)
+/// under one experiment directory. A failed repeat is recorded in the manifest and does
+/// not stop the others.
+pub async fn run_repeats<F, Fut>(
+    config: &RunnerConfig,
+    agent: &str,
+    label: &str,
+    sampling: &SamplingArgs,
+    mut run: F,
+) -> RunnerResult<()>
+where
+    F: FnMut(RunnerConfig) -> Fut,
+    Fut: Future<Output = RunnerResult<()>>,
+{
+    let mut base = config.clone();
+    if let Some(temperature) = sampling.temperature {
+        base.llm.temperature = Some(temperature);
+    }
+    if let Some(seed) = sampling.seed {
+        base.llm.seed = Some(seed);
+    }
+    if sampling.repeats <= 1 {
+        return run(base).await;
+    }
+
+    let base_seed = base.llm.seed.unwrap_or(0);
+    let created_at = Utc::now();
+    let experiment_id = format!(
+        "{}-{}-x{}",
+        created_at.format("%Y%m%dT%H%M%S"),
+        label.replace(['/', ' '], "-"),
+        sampling.repeats
+    );
+    let root = base.artifacts_dir.join(&experiment_id);
+    let mut manifest = ExperimentManifest {
+        experiment_id: experiment_id.clone(),
+        agent: agent.to_string(),
+        label: label.to_string(),
+        created_at,
+        finished_at: None,
+        provider: base.llm.provider.as_str().to_string(),
+        model: base.llm.model.clone(),
+        temperature: base.llm.temperature,
+        base_seed,
+        repeats: sampling.repeats,
+        runs: Vec::new(),
+    };
+    tracing::info!(experiment = %experiment_id, repeats = sampling.repeats, "starting experiment");
+
+    for repeat in 0..sampling.repeats {
+        let seed = base_seed.wrapping_add(u64::from(repeat));
+        let repeat_dir = PathBuf::from(format!("r{repeat:02}"));
+        let mut config = base.clone();
+        config.llm.seed = Some(seed);
+        config.artifacts_dir = root.join(&repeat_dir);
+
+        let result = run(config).await;
+        let run_dir = find_run_dir(&root.join(&repeat_dir)).map_err(RunnerError::Config)?;
+        if let Some(dir) = &run_dir {
+            let tag = ExperimentRef {
+                experiment_id: experiment_id.clone(),
+                repeat,
+                seed,
+            };
+            tag_run_meta(&dir.join("meta.json"), tag).map_err(RunnerError::Config)?;
+        }
+        if let Err(err) = &result {
+            tracing::warn!(repeat, error = %err, "experiment repeat failed");
+        }
+        manifest.runs.push(ExperimentRun {
+            repeat,
+            seed,
+            run_dir: run_dir.and_then(|dir| dir.strip_prefix(&root).ok().map(Path::to_path_buf)),
+            error: result.err().map(|err| err.to_string()),
+        });
+        write_manifest(&root, &manifest).map_err(RunnerError::Config)?;
+    }
+
+    manifest.finished_at = Some(Utc::now());
+    write_manifest(&root, &manifest).map_err(RunnerError::Config)?;
+    println!("Experiment {experiment_id} → {}", root.display());
+
+    if manifest.runs.iter().all(|run| run.error.is_some()) {
+        return Err(RunnerError::Config(anyhow!(
+            "all {} repeats failed",
+            sampling.repeats
+        )));
+    }
+    Ok(())
+}
+
+pub fn load_manifest(dir: &Path) -> Result<ExperimentManifest> {
+    let path = dir.join(MANIFEST_FILE);
+    let file = File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+    serde_json::from_reader(file)
+        .with_context(|| format!("invalid experiment manifest at {}", path.display()))
+}
+
+fn write_manifest(root: &Path, manifest: &ExperimentManifest) -> Result<()> {
+    fs::create_dir_all(root)?;
+    let file = File::create(root.join(MANIFEST_FILE))?;
+    serde_json::to_writer_pretty(file, manifest)?;
+    Ok(())
+}
+
+/// Each repeat gets its own artifacts dir, so the run folder is its only child.
+fn find_run_dir(repeat_dir: &Path) -> Result<Option<PathBuf>> {
+    if !repeat_dir.exists() {
+        return Ok(None);
+    }
+    for entry in fs::read_dir(repeat_dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+fn tag_run_meta(path: &Path, tag: ExperimentRef) -> Result<()> {
+    // A run that failed before finalize has no meta.json to tag.
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut meta: RunMetadata = serde_json::from_reader(File::open(path)?)
+        .with_context(|| format!("invalid run metadata at {}", path.display()))?;
+    meta.experiment = Some(tag);
+    serde_json::to_writer_pretty(File::create(path)?, &meta)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        artifacts::RunContext,
+        config::{test_config, test_dir},
+        redact::Redactor,
+    };
+
+    #[tokio::test]
+    async fn groups_repeats_with_consecutive_seeds() {
+        let dir = test_dir("experiment");
+        let sampling = SamplingArgs {
+            repeats: 3,
+            seed: Some(7),
+            temperature: Some(0.2),
+        };
+
+        run_repeats(
+            &test_config(dir.clone()),
+            "test",
+            "exp",
+            &sampling,
+            |config| async move {
+                assert_eq!(config.llm.temperature, Some(0.2));
+                let context = RunContext::create(config.artifacts_dir.clone(), "exp")?;
+                let mut writer = context.writer(Redactor::new(Vec::new()))?;
+                writer.finalize("test", None)?;
+                if config.llm.seed == Some(8) {
+                    return Err(RunnerError::Llm("boom".into()));
+                }
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        let root = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let manifest = load_manifest(&root).unwrap();
+        assert_eq!(
+            manifest.runs.iter().map(|r| r.seed).collect::<Vec<_>>(),
+            [7, 8, 9]
+        );
+        assert!(manifest.finished_at.is_some());
+        assert_eq!(manifest.runs[1].error.as_deref(), Some("LLM error: boom"));
+
+        let run_dir = root.join(manifest.runs[2].run_dir.as_ref().unwrap());
+        let meta: RunMetadata =
+            serde_json::from_reader(File::open(run_dir.join("meta.json")).unwrap()).unwrap();
+        let tag = meta.experiment.unwrap();
+        assert_eq!(
+            (tag.experiment_id, tag.repeat, tag.seed),
+            (manifest.experiment_id, 2, 9)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,6 +3,10 @@ pub mod artifacts;
 pub mod config;
 pub mod enso_client;
 pub mod error;
+pub mod experiment;
+pub mod llm;
+pub mod redact;
+pub mod trajectory;
 pub mod txexec;
 
 pub use artifacts::{ArtifactWriter, RunArtifact, RunContext};
@@ -11,3 +15,5 @@ pub use enso_client::{
     TransactionEnvelope,
 };
 pub use error::RunnerError;
+pub use llm::{LlmProvider, LlmUsage, OpenAiCompatible, OpenRouter};
+pub use redact::Redactor;
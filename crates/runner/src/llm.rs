@@ -0,0 +1,359 @@
+use std::{future::Future, pin::Pin, time::Duration};
+
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use crate::{
+    config::{LlmConfig, LlmProviderKind, RunnerConfig},
+    error::{RunnerError, RunnerResult},
+};
+
+pub const OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api/v1/";
+
+pub type ChatFuture<'a> = Pin<Box<dyn Future<Output = RunnerResult<Value>> + Send + 'a>>;
+
+/// A single chat-completion call. `tools` switches the call to function calling;
+/// otherwise `json_mode` requests a strict JSON object.
+#[derive(Debug, Clone, Default)]
+pub struct ChatRequest {
+    pub messages: Vec<Value>,
+    pub tools: Vec<Value>,
+    pub json_mode: bool,
+}
+
+/// A chat-completions backend. Responses are returned as the raw OpenAI-shaped body.
+pub trait LlmProvider: Send + Sync {
+    fn name(&self) -> &str;
+    fn model(&self) -> &str;
+    fn chat<'a>(&'a self, request: &'a ChatRequest) -> ChatFuture<'a>;
+}
+
+/// Sampling parameters shared by all providers.
+#[derive(Debug, Clone)]
+pub struct LlmSettings {
+    pub base_url: Url,
+    pub model: String,
+    pub temperature: Option<f32>,
+    pub seed: Option<u64>,
+    pub max_tokens: Option<u32>,
+}
+
+/// Any server speaking the OpenAI `/chat/completions` API (vLLM, llama.cpp, Ollama, ...).
+pub struct OpenAiCompatible {
+    name: String,
+    http: reqwest::Client,
+    settings: LlmSettings,
+    /// Provider-specific request fields merged into every body.
+    extra: Map<String, Value>,
+}
+
+impl OpenAiCompatible {
+    pub fn new(settings: LlmSettings, api_key: Option<&str>) -> RunnerResult<Self> {
+        Self::with_headers(
+            "openai_compatible",
+            settings,
+            api_key,
+            HeaderMap::new(),
+            Map::new(),
+        )
+    }
+
+    fn with_headers(
+        name: &str,
+        settings: LlmSettings,
+        api_key: Option<&str>,
+        mut headers: HeaderMap,
+        extra: Map<String, Value>,
+    ) -> RunnerResult<Self> {
+        if let Some(api_key) = api_key {
+            let value = HeaderValue::from_str(&format!("Bearer {api_key}"))
+                .map_err(|err| RunnerError::Llm(format!("invalid API key header: {err}")))?;
+            headers.insert(AUTHORIZATION, value);
+        }
+        let http = reqwest::Client::builder().default_headers(headers).build()?;
+        Ok(Self {
+            name: name.to_string(),
+            http,
+            settings,
+            extra,
+        })
+    }
+
+    fn body(&self, request: &ChatRequest) -> Value {
+        let mut body = json!({
+            "model": self.settings.model,
+            "messages": request.messages,
+        });
+        if !request.tools.is_empty() {
+            body["tools"] = json!(request.tools);
+            body["tool_choice"] = json!("auto");
+        } else if request.json_mode {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+        if let Some(temperature) = self.settings.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(seed) = self.settings.seed {
+            body["seed"] = json!(seed);
+        }
+        if let Some(max_tokens) = self.settings.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        for (key, value) in &self.extra {
+            body[key] = value.clone();
+        }
+        body
+    }
+
+    async fn send(&self, request: &ChatRequest) -> RunnerResult<Value> {
+        let url = self.settings.base_url.join("chat/completions")?;
+        let response = self
+            .http
+            .post(url)
+            .json(&self.body(request))
+            .send()
+            .await
+            .map_err(|err| RunnerError::Llm(format!("send error: {err}")))?
+            .error_for_status()
+            .map_err(|err| RunnerError::Llm(format!("{} HTTP error: {err}", self.name)))?;
+
+        response
+            .json()
+            .await
+            .map_err(|err| RunnerError::Llm(format!("decode error: {err}")))
+    }
+}
+
+impl LlmProvider for OpenAiCompatible {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.settings.model
+    }
+
+    fn chat<'a>(&'a self, request: &'a ChatRequest) -> ChatFuture<'a> {
+        Box::pin(self.send(request))
+    }
+}
+
+/// OpenRouter: OpenAI-compatible with a mandatory key and attribution headers.
+pub struct OpenRouter(OpenAiCompatible);
+
+impl OpenRouter {
+    pub fn new(settings: LlmSettings, api_key: &str) -> RunnerResult<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "HTTP-Referer",
+            HeaderValue::from_static("https://github.com/enso-org/ensobench"),
+        );
+        headers.insert("X-Title", HeaderValue::from_static("EnsoBench Runner"));
+        // Ask OpenRouter to report the billed cost alongside token counts.
+        let mut extra = Map::new();
+        extra.insert("usage".into(), json!({ "include": true }));
+        OpenAiCompatible::with_headers("openrouter", settings, Some(api_key), headers, extra).map(Self)
+    }
+}
+
+impl LlmProvider for OpenRouter {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn model(&self) -> &str {
+        self.0.model()
+    }
+
+    fn chat<'a>(&'a self, request: &'a ChatRequest) -> ChatFuture<'a> {
+        self.0.chat(request)
+    }
+}
+
+/// Token, latency and cost totals across the LLM calls of one run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LlmUsage {
+    pub provider: String,
+    pub model: String,
+    pub calls: u32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub latency_ms: u64,
+    /// Sum of provider-reported cost; `None` when no call reported one.
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+}
+
+impl LlmUsage {
+    pub fn new(provider: &str, model: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds the `usage` block of an OpenAI-shaped completion.
+    pub fn record(&mut self, completion: &Value, latency: Duration) {
+        let count = |key: &str| {
+            completion
+                .get("usage")
+                .and_then(|usage| usage.get(key))
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+        };
+        let prompt = count("prompt_tokens");
+        let completion_tokens = count("completion_tokens");
+        let total = match count("total_tokens") {
+            0 => prompt + completion_tokens,
+            total => total,
+        };
+
+        self.calls += 1;
+        self.prompt_tokens += prompt;
+        self.completion_tokens += completion_tokens;
+        self.total_tokens += total;
+        self.latency_ms += latency.as_millis() as u64;
+        if let Some(cost) = completion.pointer("/usage/cost").and_then(Value::as_f64) {
+            *self.cost_usd.get_or_insert(0.0) += cost;
+        }
+    }
+}
+
+/// Builds the configured provider, or `None` when it lacks what it needs to run
+/// (no OpenRouter key, no base URL for a local server).
+pub fn provider_from_config(config: &RunnerConfig) -> RunnerResult<Option<Box<dyn LlmProvider>>> {
+    let llm: &LlmConfig = &config.llm;
+    let settings = |base_url: Url| LlmSettings {
+        base_url,
+        model: llm.model.clone(),
+        temperature: llm.temperature,
+        seed: llm.seed,
+        max_tokens: llm.max_tokens,
+    };
+
+    let provider: Box<dyn LlmProvider> = match llm.provider {
+        LlmProviderKind::OpenRouter => {
+            let Some(api_key) = llm.api_key.as_deref().or(config.openrouter_api_key.as_deref()) else {
+                return Ok(None);
+            };
+            let base_url = match &llm.base_url {
+                Some(url) => url.clone(),
+                None => Url::parse(OPENROUTER_BASE_URL)?,
+            };
+            Box::new(OpenRouter::new(settings(base_url), api_key)?)
+        }
+        LlmProviderKind::OpenAiCompatible => {
+            let Some(base_url) = llm.base_url.clone() else {
+                return Ok(None);
+            };
+            Box::new(OpenAiCompatible::new(settings(base_url), llm.api_key.as_deref())?)
+        }
+    };
+    Ok(Some(provider))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    /// Serves one canned completion and hands back the raw request it received.
+    fn stub_server(reply: Value) -> (Url, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/v1/", listener.local_addr().unwrap())).unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                raw.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&raw);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let len = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= len {
+                        break;
+                    }
+                }
+            }
+            let body = reply.to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            String::from_utf8(raw).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn usage_accumulates_tokens_latency_and_cost() {
+        let mut usage = LlmUsage::new("openrouter", "openai/gpt-4.1-mini");
+        usage.record(
+            &json!({ "usage": { "prompt_tokens": 100, "completion_tokens": 20, "total_tokens": 120, "cost": 0.0015 } }),
+            Duration::from_millis(800),
+        );
+        usage.record(
+            &json!({ "usage": { "prompt_tokens": 50, "completion_tokens": 5 } }),
+            Duration::from_millis(200),
+        );
+        assert_eq!(usage.calls, 2);
+        assert_eq!(usage.total_tokens, 175);
+        assert_eq!(usage.latency_ms, 1000);
+        assert_eq!(usage.cost_usd, Some(0.0015));
+    }
+
+    #[tokio::test]
+    async fn openai_compatible_sends_settings_to_local_server() {
+        let reply = json!({ "choices": [{ "message": { "role": "assistant", "content": "{}" } }] });
+        let (base_url, server) = stub_server(reply.clone());
+        let provider = OpenAiCompatible::new(
+            LlmSettings {
+                base_url,
+                model: "local-llama".into(),
+                temperature: Some(0.0),
+                seed: Some(7),
+                max_tokens: Some(256),
+            },
+            None,
+        )
+        .unwrap();
+
+        let request = ChatRequest {
+            messages: vec![json!({ "role": "user", "content": "hi" })],
+            json_mode: true,
+            ..Default::default()
+        };
+        let response = provider.chat(&request).await.unwrap();
+        assert_eq!(response, reply);
+
+        let raw = server.join().unwrap();
+        assert!(raw.starts_with("POST /v1/chat/completions"));
+        let body: Value = serde_json::from_str(raw.split_once("\r\n\r\n").unwrap().1).unwrap();
+        assert_eq!(body["model"], "local-llama");
+        assert_eq!(body["seed"], 7);
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["response_format"]["type"], "json_object");
+        assert!(!raw.to_ascii_lowercase().contains("authorization"));
+    }
+}
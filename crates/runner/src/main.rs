@@ -1,5 +1,5 @@
 use clap::{Parser, Subcommand};
-use ensobench_runner::{agents, config::RunnerConfig, RunnerError};
+use ensobench_runner::{agents, config::RunnerConfig, experiment::run_repeats, RunnerError};
 use tracing_subscriber::EnvFilter;
 
 #[derive(Parser, Debug)]
@@ -23,6 +23,16 @@ enum Commands {
     LlmCore(agents::llm_core::Args),
     /// Execute Operation-Needle (long context) scenario
     LlmHian(agents::llm_hian::Args),
+    /// Multi-turn tool-calling coverage agent
+    LlmAgent(agents::llm_agent::Args),
+    /// Multi-iteration LLM campaign steered toward uncovered domains
+    Campaign(agents::campaign::Args),
+    /// Benchmark an external agent process speaking the stdio JSON-lines protocol
+    External(agents::external::Args),
+    /// Hand-craft plans in a REPL and commit the ones worth keeping
+    Interactive(agents::interactive::Args),
+    /// Re-issue the plans of a recorded run or plan file without an LLM
+    Replay(agents::replay::Args),
 }
 
 #[tokio::main]
@@ -36,8 +46,37 @@ async fn main() -> Result<(), RunnerError> {
     match cli.command {
         Commands::CoreRoute(args) => agents::core_route::run(&config, args).await?,
         Commands::CoreBundle(args) => agents::core_bundle::run(&config, args).await?,
-        Commands::LlmCore(args) => agents::llm_core::run(&config, args).await?,
-        Commands::LlmHian(args) => agents::llm_hian::run(&config, args).await?,
+        Commands::LlmCore(args) => {
+            run_repeats(&config, "llm_core", &args.label, &args.sampling, |config| {
+                let args = args.clone();
+                async move { agents::llm_core::run(&config, args).await }
+            })
+            .await?
+        }
+        Commands::LlmHian(args) => {
+            run_repeats(&config, "llm_hian", &args.label, &args.sampling, |config| {
+                let args = args.clone();
+                async move { agents::llm_hian::run(&config, args).await }
+            })
+            .await?
+        }
+        Commands::LlmAgent(args) => {
+            run_repeats(&config, "llm_agent", &args.label, &args.sampling, |config| {
+                let args = args.clone();
+                async move { agents::llm_agent::run(&config, args).await }
+            })
+            .await?
+        }
+        Commands::Campaign(args) => {
+            run_repeats(&config, "campaign", &args.label, &args.sampling, |config| {
+                let args = args.clone();
+                async move { agents::campaign::run(&config, args).await }
+            })
+            .await?
+        }
+        Commands::External(args) => agents::external::run(&config, args).await?,
+        Commands::Interactive(args) => agents::interactive::run(&config, args).await?,
+        Commands::Replay(args) => agents::replay::run(&config, args).await?,
     }
 
     Ok(())
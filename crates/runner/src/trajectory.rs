@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{agents::policy::PolicyViolation, artifacts::ExecutionStatus};
+
+/// One line of trajectory.jsonl. `index` counts from 0 within the run; `parent` points at
+/// the step that caused this one (tool call → tool result, Enso request → response, ...).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrajectoryStep {
+    pub index: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+    pub timestamp: DateTime<Utc>,
+    /// Wall time of the operation that produced the step (LLM call, tool call, Enso request, simulation).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(flatten)]
+    pub kind: StepKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepKind {
+    SystemPrompt {
+        text: String,
+    },
+    UserPrompt {
+        text: String,
+    },
+    /// An assistant message; planner completions carry the plan JSON in `message.content`.
+    ModelMessage {
+        message: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<TokenUsage>,
+    },
+    /// A plan that did not come from a model: offline stubs, replays and interactive commits.
+    Plan {
+        plan: Value,
+    },
+    ToolCall {
+        id: String,
+        name: String,
+        arguments: Value,
+    },
+    ToolResult {
+        id: String,
+        name: String,
+        result: Value,
+    },
+    EnsoRequest {
+        endpoint: String,
+        body: Value,
+    },
+    EnsoResponse {
+        endpoint: String,
+        #[serde(default)]
+        body: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    SimulationResult {
+        status: ExecutionStatus,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gas_used: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transaction_hash: Option<String>,
+    },
+    PolicyViolation {
+        violations: Vec<PolicyViolation>,
+    },
+    /// Runner bookkeeping without a dedicated variant (approve patches, campaign progress, ...).
+    Event {
+        name: String,
+        #[serde(default)]
+        data: Value,
+    },
+}
+
+impl StepKind {
+    pub fn event(name: &str, data: Value) -> Self {
+        Self::Event {
+            name: name.to_string(),
+            data,
+        }
+    }
+}
+
+/// Token counts of one LLM call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Reads the `usage` block of an OpenAI-shaped completion.
+    pub fn from_completion(completion: &Value) -> Option<Self> {
+        let usage = completion.get("usage")?;
+        let count = |key: &str| usage.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let (prompt_tokens, completion_tokens) = (count("prompt_tokens"), count("completion_tokens"));
+        Some(Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: match count("total_tokens") {
+                0 => prompt_tokens + completion_tokens,
+                total => total,
+            },
+        })
+    }
+}
+
+/// The pre-typed `{timestamp, role, content}` line.
+#[derive(Debug, Deserialize)]
+struct LegacyStep {
+    timestamp: DateTime<Utc>,
+    role: String,
+    content: Value,
+}
+
+/// Reads trajectory.jsonl in either format. Old `{timestamp, role, content}` lines are
+/// converted, using their line number as index and linking tool results to their calls.
+pub fn read_trajectory(data: &str) -> Result<Vec<TrajectoryStep>> {
+    let mut steps = Vec::new();
+    let mut calls = HashMap::new();
+    for (line_no, line) in data
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+    {
+        let value: Value = serde_json::from_str(line)
+            .with_context(|| format!("trajectory line {} is not JSON", line_no + 1))?;
+        let step = if value.get("type").is_some() {
+            serde_json::from_value(value)
+                .with_context(|| format!("invalid trajectory step on line {}", line_no + 1))?
+        } else {
+            let legacy: LegacyStep = serde_json::from_value(value)
+                .with_context(|| format!("invalid trajectory step on line {}", line_no + 1))?;
+            from_legacy(steps.len(), legacy, &mut calls)
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+fn from_legacy(index: usize, legacy: LegacyStep, calls: &mut HashMap<String, usize>) -> TrajectoryStep {
+    let content = legacy.content;
+    let text = |key: &str| {
+        content
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let mut parent = None;
+    let kind = match legacy.role.as_str() {
+        "planner" if content.get("choices").is_some() => StepKind::ModelMessage {
+            message: content
+                .pointer("/choices/0/message")
+                .cloned()
+                .unwrap_or_else(|| content.clone()),
+            usage: TokenUsage::from_completion(&content),
+        },
+        "planner" => StepKind::Plan { plan: content },
+        "assistant" => StepKind::ModelMessage {
+            message: content.get("message").cloned().unwrap_or(Value::Null),
+            usage: TokenUsage::from_completion(&content),
+        },
+        "tool_call" => {
+            calls.insert(text("id"), index);
+            StepKind::ToolCall {
+                id: text("id"),
+                name: text("name"),
+                arguments: content.get("arguments").cloned().unwrap_or(json!({})),
+            }
+        }
+        "tool_result" => {
+            parent = calls.get(&text("id")).copied();
+            StepKind::ToolResult {
+                id: text("id"),
+                name: text("name"),
+                result: content.get("result").cloned().unwrap_or(Value::Null),
+            }
+        }
+        "policy_violation" => StepKind::PolicyViolation {
+            violations: content
+                .get("violations")
+                .cloned()
+                .and_then(|violations| serde_json::from_value(violations).ok())
+                .unwrap_or_default(),
+        },
+        role => StepKind::event(role, content),
+    };
+    TrajectoryStep {
+        index,
+        parent,
+        timestamp: legacy.timestamp,
+        duration_ms: None,
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_typed_steps() {
+        let step = TrajectoryStep {
+            index: 3,
+            parent: Some(2),
+            timestamp: Utc::now(),
+            duration_ms: Some(41),
+            kind: StepKind::ToolResult {
+                id: "call-1".into(),
+                name: "simulate".into(),
+                result: json!({ "status": "success" }),
+            },
+        };
+        let line = serde_json::to_string(&step).unwrap();
+        assert!(line.contains(r#""type":"tool_result""#));
+        assert_eq!(read_trajectory(&line).unwrap(), [step]);
+    }
+
+    #[test]
+    fn converts_legacy_lines() {
+        let data = [
+            json!({ "timestamp": "2025-01-01T00:00:00Z", "role": "planner",
+                    "content": { "choices": [{ "message": { "content": "{}" } }],
+                                 "usage": { "prompt_tokens": 10, "completion_tokens": 5 } } }),
+            json!({ "timestamp": "2025-01-01T00:00:01Z", "role": "tool_call",
+                    "content": { "id": "c1", "name": "post_route", "arguments": {} } }),
+            json!({ "timestamp": "2025-01-01T00:00:02Z", "role": "tool_result",
+                    "content": { "id": "c1", "name": "post_route", "result": { "quoteId": 0 } } }),
+            json!({ "timestamp": "2025-01-01T00:00:03Z", "role": "approve_patch", "content": {} }),
+        ]
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        let steps = read_trajectory(&data).unwrap();
+        assert!(matches!(
+            &steps[0].kind,
+            StepKind::ModelMessage { usage: Some(usage), .. } if usage.total_tokens == 15
+        ));
+        assert_eq!((steps[2].index, steps[2].parent), (2, Some(1)));
+        assert!(matches!(&steps[3].kind, StepKind::Event { name, .. } if name == "approve_patch"));
+    }
+}
@@ -167,7 +167,7 @@ async fn wait_for_startup(client: &reqwest::Client, url: &str) -> Result<(), Run
     Ok(())
 }
 
-async fn rpc_call(
+pub(crate) async fn rpc_call(
     client: &reqwest::Client,
     url: &str,
     method: &str,
@@ -12,8 +12,13 @@ if [[ -z "$RUN_DIR" ]]; then
 fi
 
 log "Evaluating demo run at $RUN_DIR"
-cargo run -p ensobench-evaluator -- \
-  --per-tx "$RUN_DIR/per_tx.jsonl" \
+# The demo run is not simulated, so score its quotes as-is.
+cargo run -p ensobench-evaluator -- score \
+  --run "$RUN_DIR" \
   --domains dataset/domains.enso.yaml \
-  --lc-ground-truth dataset/lc/swap_usdc_weth/ground_truth.json \
+  --count-unsimulated \
+  --format json
+cargo run -p ensobench-evaluator -- verify \
+  --run "$RUN_DIR" \
+  --ground-truth dataset/lc/swap_usdc_weth/ground_truth.json \
   --format json